@@ -0,0 +1,150 @@
+//! Comparing findings with an earlier report (`sast diff`, `--compare-to`):
+//! findings are matched by fingerprint and marked new, unchanged or fixed,
+//! so a review shows what a change introduced rather than the full list.
+
+use crate::filter::FilterCount;
+use crate::metadata::{BaselineSummary, ScanMetadata};
+use crate::model::{BaselineState, Finding, Severity};
+use anyhow::{ensure, Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// The JSON report (`-f json`) as written by [`crate::output::json`].
+#[derive(serde::Deserialize)]
+struct JsonReport {
+    #[serde(default)]
+    metadata: Option<ScanMetadata>,
+    findings: Vec<Finding>,
+}
+
+/// Read a JSON report, given as the file or the report directory holding
+/// `report.json`.
+pub async fn load(path: &Path) -> Result<(Option<ScanMetadata>, Vec<Finding>)> {
+    let file = if path.is_dir() {
+        path.join("report.json")
+    } else {
+        path.to_path_buf()
+    };
+    let text = tokio::fs::read_to_string(&file)
+        .await
+        .with_context(|| format!("Cannot read report '{}'", file.display()))?;
+    let report: JsonReport = serde_json::from_str(&text).with_context(|| {
+        format!(
+            "'{}' is not a JSON report (written with `-f json`)",
+            file.display()
+        )
+    })?;
+    ensure!(
+        report.findings.iter().all(|f| !f.fingerprint.is_empty()),
+        "'{}' has findings without fingerprints; scan again to compare with it",
+        file.display()
+    );
+    Ok((report.metadata, report.findings))
+}
+
+pub struct Comparison {
+    /// Current findings, each marked new or unchanged, in their own order
+    pub findings: Vec<Finding>,
+    /// Findings of the earlier report that are gone, marked fixed
+    pub fixed: Vec<Finding>,
+    pub summary: BaselineSummary,
+}
+
+/// Match `current` against the `earlier` findings of `report`. Each earlier
+/// finding matches at most one current finding, so a weakness that now
+/// occurs twice counts once as new. Fingerprints of correlated findings
+/// count too, so a finding still matches when another tool now reports it
+/// first.
+pub fn compare(earlier: Vec<Finding>, current: Vec<Finding>, report: &Path) -> Comparison {
+    let mut index: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, f) in earlier.iter().enumerate() {
+        for fingerprint in fingerprints(f) {
+            index.entry(fingerprint).or_default().push(i);
+        }
+    }
+
+    let mut matched = vec![false; earlier.len()];
+    let states: Vec<BaselineState> = current
+        .iter()
+        .map(|f| {
+            let found = fingerprints(f)
+                .filter_map(|fp| index.get(fp))
+                .flatten()
+                .find(|i| !matched[**i]);
+            match found {
+                Some(i) => {
+                    matched[*i] = true;
+                    BaselineState::Unchanged
+                }
+                None => BaselineState::New,
+            }
+        })
+        .collect();
+
+    let findings: Vec<Finding> = current
+        .into_iter()
+        .zip(states)
+        .map(|(mut f, state)| {
+            f.baseline_state = Some(state);
+            f
+        })
+        .collect();
+    let fixed: Vec<Finding> = earlier
+        .into_iter()
+        .zip(matched)
+        .filter(|(_, matched)| !matched)
+        .map(|(mut f, _)| {
+            f.baseline_state = Some(BaselineState::Fixed);
+            f
+        })
+        .collect();
+
+    let new = findings
+        .iter()
+        .filter(|f| f.baseline_state == Some(BaselineState::New))
+        .count();
+    Comparison {
+        summary: BaselineSummary {
+            report: PathBuf::from(report),
+            new,
+            fixed: fixed.len(),
+            unchanged: findings.len() - new,
+        },
+        findings,
+        fixed,
+    }
+}
+
+impl Comparison {
+    /// Drop the unchanged findings (`--new-only`); the count is recorded
+    /// like those of the filters.
+    pub fn new_only(&mut self) -> FilterCount {
+        let before = self.findings.len();
+        self.findings
+            .retain(|f| f.baseline_state == Some(BaselineState::New));
+        FilterCount {
+            filter: "new-only".into(),
+            removed: before - self.findings.len(),
+        }
+    }
+}
+
+/// Fail when a new finding is at least `min` severe (`--fail-on-new`).
+pub fn gate(findings: &[Finding], min: Severity) -> Result<()> {
+    let blocking = findings
+        .iter()
+        .filter(|f| f.baseline_state == Some(BaselineState::New))
+        .filter(|f| f.severity.rank() >= min.rank())
+        .count();
+    ensure!(
+        blocking == 0,
+        "{blocking} new finding(s) at or above severity {min}"
+    );
+    Ok(())
+}
+
+fn fingerprints(f: &Finding) -> impl Iterator<Item = &str> {
+    std::iter::once(f.fingerprint.as_str())
+        .chain(f.correlated.iter().map(|c| c.fingerprint.as_str()))
+        .filter(|fp| !fp.is_empty())
+}
@@ -0,0 +1,284 @@
+//! On-disk cache of tool runs (`~/.cache/sast`). A recorded run is reused
+//! while the tool, its configuration and version, the rule and config files
+//! its arguments name, and the contents of the project's files are
+//! unchanged. Tools marked `cache_per_file` are cached
+//! per source, so editing one re-runs the tool on that file only.
+
+use crate::config::ToolConfig;
+use crate::replay;
+use crate::runner::ToolRun;
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Overrides where the cache lives, e.g. to keep it with CI caches
+pub const DIR_ENV: &str = "SAST_CACHE_DIR";
+
+/// Bumped when the key material or the entry layout changes
+const KEY_VERSION: &str = "sast-cache-v1";
+
+/// Files a per-file run may include besides the source it's given
+const HEADER_EXTENSIONS: &[&str] = &[
+    "h", "hh", "hpp", "hxx", "h++", "inc", "inl", "ipp", "tcc", "tpp",
+];
+
+/// Where entries are kept: `$SAST_CACHE_DIR`, else `sast/` in the user's
+/// cache directory.
+pub fn dir() -> Option<PathBuf> {
+    std::env::var_os(DIR_ENV)
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| dirs::cache_dir().map(|d| d.join("sast")))
+}
+
+/// Remove every entry under `dir`; returns how many there were. Only the
+/// cache's own shard directories are touched, whatever `dir` points at.
+pub fn clean(dir: &Path) -> Result<usize> {
+    if !dir.is_dir() {
+        return Ok(0);
+    }
+    let mut removed = 0;
+    for shard in std::fs::read_dir(dir)? {
+        let shard = shard?;
+        let name = shard.file_name().to_string_lossy().to_string();
+        if !shard.file_type()?.is_dir() || !is_shard(&name) {
+            continue;
+        }
+        removed += std::fs::read_dir(shard.path())?.count();
+        std::fs::remove_dir_all(shard.path())
+            .with_context(|| format!("Cannot remove '{}'", shard.path().display()))?;
+    }
+    Ok(removed)
+}
+
+fn is_shard(name: &str) -> bool {
+    name.len() == 2 && name.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// The cache as seen by one scan: entries plus the hashes of the project's
+/// files when the scan started.
+pub struct Cache {
+    dir: PathBuf,
+    /// Content hash of every scanned file, relative to the project
+    files: BTreeMap<PathBuf, String>,
+}
+
+/// What every entry of one tool in one scan shares: the tool's name,
+/// configuration and version, where it runs from, and the files outside
+/// the project its arguments name.
+pub struct ToolKey(String);
+
+impl ToolKey {
+    /// `None` when a file the arguments name can't be read.
+    pub fn new(
+        name: &str,
+        config: &ToolConfig,
+        version: &str,
+        project_path: &Path,
+    ) -> Option<Self> {
+        let config_json = serde_json::to_value(config)
+            .map(|v| v.to_string())
+            .unwrap_or_default();
+        // Tools print paths relative to where they ran, and cached output
+        // is normalized like fresh output
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let cwd = cwd.to_string_lossy();
+        let project = project_path.to_string_lossy();
+        let mut parts = vec![
+            KEY_VERSION,
+            env!("CARGO_PKG_VERSION"),
+            name,
+            &config_json,
+            version,
+            &cwd,
+            &project,
+        ];
+        let inputs = external_inputs(config, project_path)?;
+        for (arg, hash) in &inputs {
+            parts.extend([arg.as_str(), hash.as_str()]);
+        }
+        Some(Self(digest(&parts)))
+    }
+}
+
+/// Files and directories named by `config.args`, as an argument or after
+/// `=` (`--config=rules/`), that lie outside the project, with a hash of
+/// their contents; the project's own files are keyed anyway.
+fn external_inputs(config: &ToolConfig, project_path: &Path) -> Option<Vec<(String, String)>> {
+    let root = std::fs::canonicalize(project_path).ok()?;
+    let mut inputs = Vec::new();
+    for arg in &config.args {
+        let value = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with('-') => value,
+            _ => arg.as_str(),
+        };
+        let Ok(path) = std::fs::canonicalize(value) else {
+            continue;
+        };
+        if path.starts_with(&root) {
+            continue;
+        }
+        let hash = if path.is_dir() {
+            let files = hash_tree(&path, None).ok()?;
+            let mut parts = Vec::new();
+            for (file, hash) in &files {
+                parts.extend([file.to_str().unwrap_or_default(), hash.as_str()]);
+            }
+            digest(&parts)
+        } else {
+            hash_file(&path).ok()?
+        };
+        inputs.push((value.to_string(), hash));
+    }
+    Some(inputs)
+}
+
+impl Cache {
+    /// Hash every file under `project_path` but those in `output_dir`,
+    /// which tools write into. `None` when there's no cache directory.
+    pub fn open(project_path: &Path, output_dir: &Path) -> Result<Option<Self>> {
+        let Some(dir) = dir() else {
+            return Ok(None);
+        };
+        let files = if project_path.is_dir() {
+            let output_dir = std::fs::canonicalize(output_dir).ok();
+            let root = std::fs::canonicalize(project_path)
+                .with_context(|| format!("Cannot read '{}'", project_path.display()))?;
+            hash_tree(&root, output_dir.as_deref())?
+        } else {
+            BTreeMap::from([(PathBuf::new(), hash_file(project_path)?)])
+        };
+        Ok(Some(Self { dir, files }))
+    }
+
+    /// Key of a run over the whole project, given the project-relative
+    /// `sources` (empty unless the tool takes a file list).
+    pub fn run_key(&self, tool: &ToolKey, sources: &[PathBuf]) -> String {
+        let mut parts = vec![tool.0.as_str(), "run"];
+        for file in sources {
+            parts.push(file.to_str().unwrap_or_default());
+        }
+        parts.push("files");
+        for (path, hash) in &self.files {
+            parts.extend([path.to_str().unwrap_or_default(), hash.as_str()]);
+        }
+        digest(&parts)
+    }
+
+    /// Hash of the files a per-file run may read besides the one it's given:
+    /// the headers that aren't among the tool's `sources`.
+    pub fn context(&self, sources: &[PathBuf]) -> String {
+        let sources: HashSet<&PathBuf> = sources.iter().collect();
+        let is_header = |path: &Path| {
+            path.extension().is_some_and(|e| {
+                HEADER_EXTENSIONS.contains(&e.to_string_lossy().to_lowercase().as_str())
+            })
+        };
+        let mut parts = vec!["context"];
+        for (path, hash) in &self.files {
+            if is_header(path) && !sources.contains(path) {
+                parts.extend([path.to_str().unwrap_or_default(), hash.as_str()]);
+            }
+        }
+        digest(&parts)
+    }
+
+    /// Key of a run on the project-relative `file`; `None` for files that
+    /// weren't hashed.
+    pub fn file_key(&self, tool: &ToolKey, context: &str, file: &Path) -> Option<String> {
+        let hash = self.files.get(file)?;
+        Some(digest(&[
+            tool.0.as_str(),
+            "file",
+            context,
+            file.to_str()?,
+            hash,
+        ]))
+    }
+
+    fn entry(&self, key: &str) -> PathBuf {
+        self.dir.join(&key[..2]).join(key)
+    }
+
+    pub async fn get(&self, key: &str) -> Option<ToolRun> {
+        let dir = self.entry(key);
+        if !dir.join(replay::MANIFEST_FILE).exists() {
+            return None;
+        }
+        match replay::load(&dir).await {
+            Ok((_, mut runs)) if runs.len() == 1 => runs.pop(),
+            Ok(_) => None,
+            Err(e) => {
+                tracing::warn!("Ignoring broken cache entry {}: {e:#}", dir.display());
+                None
+            }
+        }
+    }
+
+    /// Store `run` under `key`. Entries are written aside and renamed into
+    /// place, so a concurrent scan never reads half an entry.
+    pub async fn put(&self, key: &str, run: &ToolRun, project_path: &Path) -> Result<()> {
+        let dir = self.entry(key);
+        let tmp = dir.with_extension(format!("tmp{}", std::process::id()));
+        replay::write(std::slice::from_ref(run), &tmp, project_path, None).await?;
+        if tokio::fs::rename(&tmp, &dir).await.is_err() {
+            // Entries are only stored after `get` found none, so one in the
+            // way is broken; an equal one from a concurrent scan is replaced
+            let _ = tokio::fs::remove_dir_all(&dir).await;
+            let renamed = tokio::fs::rename(&tmp, &dir).await;
+            if renamed.is_err() {
+                let _ = tokio::fs::remove_dir_all(&tmp).await;
+            }
+            renamed.with_context(|| format!("Cannot store '{}'", dir.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Content hash of every file under `root` but those in `skip`, relative
+/// to `root`. Version control metadata is left out: it changes without the
+/// sources changing (`git status` alone rewrites the index). Symlinked
+/// files are hashed, symlinked directories aren't followed.
+fn hash_tree(root: &Path, skip: Option<&Path>) -> Result<BTreeMap<PathBuf, String>> {
+    let mut files = BTreeMap::new();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let entries =
+            std::fs::read_dir(&dir).with_context(|| format!("Cannot read '{}'", dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if skip.is_some_and(|skip| path.starts_with(skip)) {
+                continue;
+            }
+            let Ok(file_type) = std::fs::symlink_metadata(&path).map(|m| m.file_type()) else {
+                continue;
+            };
+            if file_type.is_dir() {
+                let name = path.file_name().unwrap_or_default();
+                if ![".git", ".hg", ".svn"].iter().any(|vcs| name == *vcs) {
+                    stack.push(path);
+                }
+            } else if file_type.is_file() || path.is_file() {
+                let rel = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+                files.insert(rel, hash_file(&path)?);
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn hash_file(path: &Path) -> Result<String> {
+    let data = std::fs::read(path).with_context(|| format!("Cannot read '{}'", path.display()))?;
+    Ok(format!("{:x}", Sha256::digest(data)))
+}
+
+/// Hash of `parts`, each terminated so that no two lists collide.
+fn digest(parts: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())
+}
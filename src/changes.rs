@@ -0,0 +1,166 @@
+//! Incremental scans: the files and lines changed since a git ref, used to
+//! narrow what tools are given and which findings are reported.
+
+use crate::filter::FilterCount;
+use crate::model::Finding;
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+/// Changes between the merge base of a ref and the work tree.
+#[derive(Debug)]
+pub struct ChangeSet {
+    /// Added or modified lines of each changed file, relative to the project
+    files: BTreeMap<PathBuf, Vec<RangeInclusive<u32>>>,
+}
+
+impl ChangeSet {
+    /// What changed in the project since it branched off `base`, including
+    /// uncommitted and untracked files.
+    pub async fn since(project_path: &Path, base: &str) -> Result<Self> {
+        let root = git(project_path, &["rev-parse", "--show-toplevel"])
+            .await
+            .context("--changed-since needs the project to be in a git work tree")?;
+        let root = PathBuf::from(root.trim());
+        let merge_base = git(project_path, &["merge-base", base, "HEAD"])
+            .await
+            .with_context(|| format!("Cannot find where HEAD branched off '{base}'"))?;
+        let diff = git(
+            project_path,
+            &[
+                "-c",
+                "core.quotepath=off",
+                "diff",
+                "--unified=0",
+                "--no-color",
+                "--no-ext-diff",
+                "--diff-filter=ACMR",
+                merge_base.trim(),
+                "--",
+                ".",
+            ],
+        )
+        .await?;
+        let untracked = git(
+            project_path,
+            &["ls-files", "--others", "--exclude-standard", "--full-name"],
+        )
+        .await?;
+
+        let project = std::fs::canonicalize(project_path)
+            .with_context(|| format!("Cannot resolve '{}'", project_path.display()))?;
+        let root = std::fs::canonicalize(&root).unwrap_or(root);
+        let relative = |path: &str| -> Option<PathBuf> {
+            root.join(path)
+                .strip_prefix(&project)
+                .ok()
+                .map(Path::to_path_buf)
+        };
+
+        let mut files: BTreeMap<PathBuf, Vec<RangeInclusive<u32>>> = BTreeMap::new();
+        let mut current = None;
+        for line in diff.lines() {
+            if let Some(path) = line.strip_prefix("+++ ") {
+                current = path.strip_prefix("b/").and_then(relative);
+                if let Some(file) = &current {
+                    files.entry(file.clone()).or_default();
+                }
+            } else if let Some(hunk) = line.strip_prefix("@@ ") {
+                if let (Some(file), Some(range)) = (&current, added_lines(hunk)) {
+                    files.entry(file.clone()).or_default().push(range);
+                }
+            }
+        }
+        // New files: every line counts as added
+        for path in untracked.lines().filter_map(relative) {
+            files.insert(path, vec![1..=u32::MAX]);
+        }
+        Ok(Self { files })
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether the project-relative `file` changed.
+    pub fn contains(&self, file: &Path) -> bool {
+        self.files.contains_key(file)
+    }
+
+    /// Whether any line of `f`'s range was added or modified.
+    fn touches(&self, f: &Finding) -> bool {
+        let (Some(ranges), Some(start)) = (self.files.get(&f.location.file), f.location.line)
+        else {
+            return false;
+        };
+        let end = f.location.end_line.unwrap_or(start).max(start);
+        ranges
+            .iter()
+            .any(|r| *r.start() <= end && start <= *r.end())
+    }
+
+    /// Keep the findings in changed files, or on changed lines with
+    /// `lines_only`. Counts are recorded like those of the other filters.
+    pub fn filter(
+        &self,
+        findings: Vec<Finding>,
+        lines_only: bool,
+    ) -> (Vec<Finding>, Vec<FilterCount>) {
+        let (mut outside_files, mut outside_lines) = (0, 0);
+        let kept = findings
+            .into_iter()
+            .filter(|f| {
+                if !self.contains(&f.location.file) {
+                    outside_files += 1;
+                    false
+                } else if lines_only && !self.touches(f) {
+                    outside_lines += 1;
+                    false
+                } else {
+                    true
+                }
+            })
+            .collect();
+        let mut counts = vec![FilterCount {
+            filter: "changed-since".into(),
+            removed: outside_files,
+        }];
+        if lines_only {
+            counts.push(FilterCount {
+                filter: "changed-lines-only".into(),
+                removed: outside_lines,
+            });
+        }
+        (kept, counts)
+    }
+}
+
+/// New-side lines of a hunk header such as `-12,3 +14,5 @@ fn main()`.
+/// Pure deletions add no lines.
+fn added_lines(hunk: &str) -> Option<RangeInclusive<u32>> {
+    let new = hunk.split_whitespace().find_map(|p| p.strip_prefix('+'))?;
+    let (start, count) = match new.split_once(',') {
+        Some((start, count)) => (start.parse::<u32>().ok()?, count.parse::<u32>().ok()?),
+        None => (new.parse::<u32>().ok()?, 1),
+    };
+    (count > 0).then(|| start..=start + count - 1)
+}
+
+async fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = tokio::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .await
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
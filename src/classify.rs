@@ -0,0 +1,90 @@
+//! Kind and security severity of findings: from the tool's own metadata when
+//! it rates the finding, else from its CWEs and the tables below.
+
+use crate::model::{Finding, Kind, SecuritySeverity, Severity};
+
+/// Weaknesses that are vulnerabilities, by how bad exploiting them usually is
+const SECURITY_CWES: &[(SecuritySeverity, &[u32])] = &[
+    (
+        SecuritySeverity::Critical,
+        // Injection and unbounded writes
+        &[77, 78, 89, 94, 120, 121, 122, 134, 787],
+    ),
+    (
+        SecuritySeverity::High,
+        &[22, 79, 119, 125, 190, 242, 415, 416, 611, 788, 798, 825],
+    ),
+    (
+        SecuritySeverity::Medium,
+        &[
+            327, 338, 369, 401, 457, 476, 676, 690, 762, 772, 775, 908, 909,
+        ],
+    ),
+    (SecuritySeverity::Low, &[477]),
+];
+
+/// Weaknesses that make code wrong without being exploitable as such
+const BUG_CWES: &[u32] = &[562, 685, 686, 704, 758];
+
+/// Dead or redundant code
+const CODE_SMELL_CWES: &[u32] = &[561, 563, 1164];
+
+/// Fill in `kind` and `security_severity` where the parser left them unset.
+pub fn assign(findings: &mut [Finding]) {
+    for f in findings {
+        let (kind, security_severity) = classify(f);
+        f.kind.get_or_insert(kind);
+        if f.kind == Some(Kind::Vulnerability) && f.security_severity.is_none() {
+            f.security_severity = security_severity;
+        }
+    }
+}
+
+fn classify(f: &Finding) -> (Kind, Option<SecuritySeverity>) {
+    let from_cwe = f.cwe.iter().filter_map(|c| security_severity(*c)).max();
+
+    // semgrep's rule metadata: `category: security` plus an `impact` rating
+    if f.properties.get("category").and_then(|c| c.as_str()) == Some("security") {
+        let impact = f
+            .properties
+            .get("impact")
+            .and_then(|i| i.as_str())
+            .and_then(|i| match i.to_ascii_uppercase().as_str() {
+                "HIGH" => Some(SecuritySeverity::High),
+                "MEDIUM" => Some(SecuritySeverity::Medium),
+                "LOW" => Some(SecuritySeverity::Low),
+                _ => None,
+            });
+        let by_severity = match f.severity {
+            Severity::Error => SecuritySeverity::High,
+            Severity::Warning => SecuritySeverity::Medium,
+            _ => SecuritySeverity::Low,
+        };
+        return (
+            Kind::Vulnerability,
+            Some(impact.or(from_cwe).unwrap_or(by_severity)),
+        );
+    }
+
+    if from_cwe.is_some() {
+        return (Kind::Vulnerability, from_cwe);
+    }
+    let kind = if f.cwe.iter().any(|c| BUG_CWES.contains(c)) {
+        Kind::Bug
+    } else if f.cwe.iter().any(|c| CODE_SMELL_CWES.contains(c)) {
+        Kind::CodeSmell
+    } else {
+        match f.severity {
+            Severity::Error | Severity::Warning => Kind::Bug,
+            Severity::Style | Severity::Info | Severity::Note => Kind::CodeSmell,
+        }
+    };
+    (kind, None)
+}
+
+fn security_severity(cwe: u32) -> Option<SecuritySeverity> {
+    SECURITY_CWES
+        .iter()
+        .find(|(_, cwes)| cwes.contains(&cwe))
+        .map(|(level, _)| *level)
+}
@@ -1,25 +1,193 @@
-use clap::Parser;
+use crate::model::Severity;
+use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
-#[command(name = "sast", about = "Run SAST tools and aggregate results")]
+#[command(
+    name = "sast",
+    about = "Run SAST tools and aggregate results",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Path to the project to analyze
-    pub path: PathBuf,
+    #[arg(required = true)]
+    pub path: Option<PathBuf>,
 
-    /// Output format: native, sarif, html, json [default: native]
-    #[arg(short, long)]
-    pub format: Option<String>,
+    /// Comma-separated output formats: native, sarif, html, json, markdown, gitlab-sast, gitlab-codequality, junit, checkstyle, sonar, csv, jsonl, pretty, template [default: native]
+    #[arg(short, long, value_delimiter = ',')]
+    pub format: Option<Vec<String>>,
 
-    /// Output folder (default: <path>/sast_report/)
+    /// Output folder (default: <path>/sast_report/), or `-` for stdout
     #[arg(short, long)]
     pub output: Option<PathBuf>,
 
-    /// Comma-separated list of tools to run (overrides config)
+    /// Write the report to stdout (same as `-o -`); needs a single format
+    #[arg(long, conflicts_with = "output")]
+    pub stdout: bool,
+
+    /// Comma-separated list of tools to run, or `auto` (overrides config)
     #[arg(short, long, value_delimiter = ',')]
     pub tools: Option<Vec<String>>,
 
     /// Path to config file
     #[arg(short, long)]
     pub config: Option<PathBuf>,
+
+    /// Save raw tool outputs and a manifest under <output>/raw/ for `sast replay`
+    #[arg(long)]
+    pub save_raw: bool,
+
+    /// Only scan files changed since this git ref (its merge base with HEAD)
+    #[arg(long, value_name = "REF")]
+    pub changed_since: Option<String>,
+
+    /// With --changed-since, only report findings on added or modified lines
+    #[arg(long, requires = "changed_since")]
+    pub changed_lines_only: bool,
+
+    /// Run every tool instead of reusing cached results
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Compare the findings with an earlier JSON report and mark them new or
+    /// unchanged; findings that are gone are reported as fixed
+    #[arg(long, value_name = "REPORT")]
+    pub compare_to: Option<PathBuf>,
+
+    /// With --compare-to, only report new findings
+    #[arg(long, requires = "compare_to")]
+    pub new_only: bool,
+
+    /// With --compare-to, exit with an error when a new finding is at least SEVERITY [default: note]
+    #[arg(long, value_name = "SEVERITY", num_args = 0..=1, default_missing_value = "note", requires = "compare_to")]
+    pub fail_on_new: Option<Severity>,
+
+    #[command(flatten)]
+    pub correlation: CorrelationArgs,
+
+    #[command(flatten)]
+    pub filters: FilterArgs,
+}
+
+/// Cross-tool deduplication, shared by scans and replays
+#[derive(clap::Args, Debug)]
+pub struct CorrelationArgs {
+    /// Report every tool's findings separately instead of merging duplicates
+    #[arg(long)]
+    pub no_dedup: bool,
+
+    /// Only report findings confirmed by at least N tools [default: 2]
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "2", conflicts_with = "no_dedup")]
+    pub consensus: Option<usize>,
+}
+
+/// Findings to leave out of the reports, added to the config's `filters`
+#[derive(clap::Args, Debug)]
+pub struct FilterArgs {
+    /// Drop findings below this severity: error, warning, style, info, note
+    #[arg(long, value_name = "SEVERITY")]
+    pub min_severity: Option<Severity>,
+
+    /// Comma-separated tools whose findings are dropped
+    #[arg(long, value_name = "TOOL", value_delimiter = ',')]
+    pub exclude_tool: Vec<String>,
+
+    /// Comma-separated rule id globs to drop, e.g. missingIncludeSystem
+    #[arg(long, value_name = "GLOB", value_delimiter = ',')]
+    pub exclude_rule: Vec<String>,
+
+    /// Comma-separated path globs; only findings in matching files are kept
+    #[arg(long, value_name = "GLOB", value_delimiter = ',')]
+    pub include_path: Vec<String>,
+
+    /// Comma-separated path globs to drop, e.g. third_party/
+    #[arg(long, value_name = "GLOB", value_delimiter = ',')]
+    pub exclude_path: Vec<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Detect project languages and write a starter sast.yaml
+    Init {
+        /// Path to the project to inspect
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Overwrite an existing sast.yaml
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Re-parse raw outputs saved with --save-raw and write reports again
+    Replay {
+        /// Directory holding manifest.json, or a report directory containing raw/
+        dir: PathBuf,
+
+        /// Comma-separated output formats: native, sarif, html, json, markdown, gitlab-sast, gitlab-codequality, junit, checkstyle, sonar, csv, jsonl, pretty, template
+        #[arg(short, long, value_delimiter = ',')]
+        format: Option<Vec<String>>,
+
+        /// Output folder (default: the report directory that was replayed), or `-` for stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Write the report to stdout (same as `-o -`); needs a single format
+        #[arg(long, conflicts_with = "output")]
+        stdout: bool,
+
+        /// Path to config file
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+
+        #[command(flatten)]
+        correlation: CorrelationArgs,
+
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+
+    /// Compare two JSON reports: which findings are new, fixed or unchanged
+    Diff {
+        /// The earlier report: report.json or the directory holding it
+        old: PathBuf,
+
+        /// The later report: report.json or the directory holding it
+        new: PathBuf,
+
+        /// Comma-separated output formats: sarif, html, json, markdown, gitlab-sast, gitlab-codequality, junit, checkstyle, sonar, csv, jsonl, pretty, template [default: pretty]
+        #[arg(short, long, value_delimiter = ',')]
+        format: Option<Vec<String>>,
+
+        /// Output folder; without it the report goes to stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Path to config file
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+
+        /// Only report new findings
+        #[arg(long)]
+        new_only: bool,
+
+        /// Exit with an error when a new finding is at least SEVERITY [default: note]
+        #[arg(long, value_name = "SEVERITY", num_args = 0..=1, default_missing_value = "note")]
+        fail_on_new: Option<Severity>,
+    },
+
+    /// Manage the cache of tool results
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheCommand {
+    /// Remove every cached tool result
+    Clean,
 }
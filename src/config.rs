@@ -1,23 +1,204 @@
+use crate::model::Severity;
 use anyhow::Result;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
-#[derive(Debug, serde::Deserialize, Default, Clone)]
+#[derive(Debug, serde::Serialize, serde::Deserialize, Default, Clone)]
 pub struct Config {
     #[serde(default)]
     pub defaults: Defaults,
     #[serde(default)]
     pub tools: HashMap<String, ToolConfig>,
+    #[serde(default)]
+    pub report: ReportConfig,
+    #[serde(default)]
+    pub correlation: CorrelationConfig,
+    #[serde(default)]
+    pub paths: PathsConfig,
+    /// Severity changes for matching findings of any tool; later entries win
+    #[serde(default)]
+    pub severity_overrides: Vec<SeverityOverride>,
+    #[serde(default)]
+    pub filters: FilterConfig,
+}
+
+/// Findings left out of every report (`filters:` in YAML). Rules and paths
+/// are globs; paths are relative to the project.
+#[derive(Debug, serde::Serialize, serde::Deserialize, Default, Clone)]
+pub struct FilterConfig {
+    pub min_severity: Option<Severity>,
+    #[serde(default)]
+    pub exclude_tools: Vec<String>,
+    #[serde(default)]
+    pub exclude_rules: Vec<String>,
+    /// When set, only findings in matching files are kept
+    #[serde(default)]
+    pub include_paths: Vec<String>,
+    #[serde(default)]
+    pub exclude_paths: Vec<String>,
+}
+
+/// Findings matching every given key of an entry in `severity_overrides`
+/// get its `severity`.
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct SeverityOverride {
+    pub tool: Option<String>,
+    /// Glob over the rule id, e.g. `*cpp-unsafe-gets`
+    pub rule: Option<String>,
+    pub cwe: Option<u32>,
+    pub severity: Severity,
+}
+
+/// How tool-reported paths map onto the project (`paths:` in YAML).
+#[derive(Debug, serde::Serialize, serde::Deserialize, Default, Clone)]
+pub struct PathsConfig {
+    /// Path prefixes the tools saw, e.g. `/workspace` inside a build
+    /// container, mapped to local paths; relative targets are resolved
+    /// against the project root
+    #[serde(default)]
+    pub remap: HashMap<String, String>,
+}
+
+/// Cross-tool deduplication (`correlation:` in YAML).
+#[derive(Debug, serde::Serialize, serde::Deserialize, Default, Clone)]
+pub struct CorrelationConfig {
+    /// Merge findings of different tools about the same weakness (default: true)
+    pub enabled: Option<bool>,
+    /// How many lines apart correlated findings may be (default: 2)
+    pub line_window: Option<u32>,
+    /// Report only findings confirmed by at least this many tools (default: 1)
+    pub min_tools: Option<usize>,
 }
 
-#[derive(Debug, serde::Deserialize, Default, Clone)]
+#[derive(Debug, serde::Serialize, serde::Deserialize, Default, Clone)]
 pub struct Defaults {
+    /// Tool names, or `auto` to pick tools from the project's languages
+    #[serde(default, deserialize_with = "one_or_many")]
     pub tools: Option<Vec<String>>,
-    pub format: Option<String>,
+    /// One output format or a list of them
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub format: Option<Vec<String>>,
     pub output: Option<PathBuf>,
+    /// Keep raw tool outputs and a manifest under `<output>/raw/`
+    pub save_raw: Option<bool>,
+    /// Reuse cached tool results when nothing they depend on changed
+    /// (default: true)
+    pub cache: Option<bool>,
+}
+
+/// Per-format report options (`report:` in YAML).
+#[derive(Debug, serde::Serialize, serde::Deserialize, Default, Clone)]
+pub struct ReportConfig {
+    #[serde(default)]
+    pub markdown: MarkdownConfig,
+    #[serde(default)]
+    pub junit: JunitConfig,
+    #[serde(default)]
+    pub checkstyle: CheckstyleConfig,
+    #[serde(default)]
+    pub sonar: SonarConfig,
+    #[serde(default)]
+    pub csv: CsvConfig,
+    #[serde(default)]
+    pub template: TemplateConfig,
+    /// Report file name per format, e.g. `sarif: "{project}-{date}.sarif"`
+    #[serde(default)]
+    pub file_names: HashMap<String, String>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Default, Clone)]
+pub struct MarkdownConfig {
+    /// Link for a finding location; `{path}` (percent-encoded) and `{line}`
+    /// are interpolated, e.g. `https://github.com/org/repo/blob/main/{path}#L{line}`
+    pub link_template: Option<String>,
+    /// Maximum size of the report in bytes (default: 60000, under GitHub's
+    /// comment limit)
+    pub max_bytes: Option<usize>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Default, Clone)]
+pub struct JunitConfig {
+    /// Findings at or above this severity fail their testcase (default: warning)
+    pub min_severity: Option<Severity>,
+    /// What a testcase stands for within a tool's testsuite (default: rule)
+    pub testcase: Option<JunitTestcase>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JunitTestcase {
+    Rule,
+    File,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Default, Clone)]
+pub struct CheckstyleConfig {
+    /// Checkstyle severity (`error`, `warning`, `info`, `ignore`) per sast severity
+    #[serde(default)]
+    pub severity_map: HashMap<Severity, String>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Default, Clone)]
+pub struct SonarConfig {
+    /// Sonar severity (`BLOCKER`, `CRITICAL`, `MAJOR`, `MINOR`, `INFO`) per sast severity
+    #[serde(default)]
+    pub severity_map: HashMap<Severity, String>,
+    /// Sonar issue type (`BUG`, `VULNERABILITY`, `CODE_SMELL`) per sast severity
+    #[serde(default)]
+    pub type_map: HashMap<Severity, String>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Default, Clone)]
+pub struct CsvConfig {
+    /// Columns to write, from the flat finding layout (default: all of them)
+    pub columns: Option<Vec<String>>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Default, Clone)]
+pub struct TemplateConfig {
+    /// Jinja-style template rendered by the `template` format
+    pub path: Option<PathBuf>,
+    /// File name of the rendered report in the output directory (default:
+    /// the template's name without `.j2`/`.jinja`/`.jinja2`)
+    pub output: Option<String>,
+}
+
+impl ReportConfig {
+    fn merge(&mut self, other: ReportConfig) {
+        let markdown = other.markdown;
+        if markdown.link_template.is_some() {
+            self.markdown.link_template = markdown.link_template;
+        }
+        if markdown.max_bytes.is_some() {
+            self.markdown.max_bytes = markdown.max_bytes;
+        }
+        let junit = other.junit;
+        if junit.min_severity.is_some() {
+            self.junit.min_severity = junit.min_severity;
+        }
+        if junit.testcase.is_some() {
+            self.junit.testcase = junit.testcase;
+        }
+        self.checkstyle
+            .severity_map
+            .extend(other.checkstyle.severity_map);
+        self.sonar.severity_map.extend(other.sonar.severity_map);
+        self.sonar.type_map.extend(other.sonar.type_map);
+        if other.csv.columns.is_some() {
+            self.csv.columns = other.csv.columns;
+        }
+        if other.template.path.is_some() {
+            self.template.path = other.template.path;
+        }
+        if other.template.output.is_some() {
+            self.template.output = other.template.output;
+        }
+        self.file_names.extend(other.file_names);
+    }
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ToolConfig {
     pub command: String,
     #[serde(default)]
@@ -26,9 +207,24 @@ pub struct ToolConfig {
     pub output_stream: OutputStream,
     pub native_extension: Option<String>,
     pub append_sources: Option<String>,
+    /// Languages the tool analyzes; empty means any
+    #[serde(default)]
+    pub languages: Vec<String>,
+    /// Globs (relative to the project) of files the tool applies to; empty means any
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// The tool's own severity levels (e.g. cppcheck's `performance`) mapped
+    /// to ours; `"*"` covers any other level and tools that report none
+    #[serde(default)]
+    pub severity_map: HashMap<String, Severity>,
+    /// Cache results per file of `append_sources`, running the tool once per
+    /// changed file. Their outputs are joined, so only for tools printing
+    /// one diagnostic per line (compilers, not JSON or XML reports)
+    #[serde(default)]
+    pub cache_per_file: bool,
 }
 
-#[derive(Debug, Clone, serde::Deserialize, Default)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum OutputStream {
     #[default]
@@ -38,6 +234,16 @@ pub enum OutputStream {
 }
 
 impl Config {
+    /// Hash of the resolved configuration, to tell apart reports produced
+    /// with different settings.
+    pub fn hash(&self) -> String {
+        // serde_json maps are sorted, which makes the encoding canonical
+        let canonical = serde_json::to_value(self)
+            .map(|v| v.to_string())
+            .unwrap_or_default();
+        format!("{:x}", Sha256::digest(canonical))
+    }
+
     pub fn builtin_defaults() -> Self {
         let mut tools = HashMap::new();
 
@@ -54,6 +260,10 @@ impl Config {
                 output_stream: OutputStream::Stderr,
                 native_extension: Some("txt".into()),
                 append_sources: None,
+                languages: vec!["c".into(), "cpp".into()],
+                files: Vec::new(),
+                severity_map: HashMap::new(),
+                cache_per_file: false,
             },
         );
 
@@ -73,6 +283,10 @@ impl Config {
                 output_stream: OutputStream::Filesystem,
                 native_extension: Some("html".into()),
                 append_sources: Some("*.cpp".into()),
+                languages: vec!["cpp".into()],
+                files: Vec::new(),
+                severity_map: HashMap::new(),
+                cache_per_file: false,
             },
         );
 
@@ -88,6 +302,10 @@ impl Config {
                 output_stream: OutputStream::Stdout,
                 native_extension: Some("json".into()),
                 append_sources: None,
+                languages: Vec::new(),
+                files: Vec::new(),
+                severity_map: HashMap::new(),
+                cache_per_file: false,
             },
         );
 
@@ -98,10 +316,17 @@ impl Config {
                     "scan-build".into(),
                     "semgrep".into(),
                 ]),
-                format: Some("native".into()),
+                format: Some(vec!["native".into()]),
                 output: None,
+                save_raw: None,
+                cache: None,
             },
             tools,
+            report: ReportConfig::default(),
+            correlation: CorrelationConfig::default(),
+            paths: PathsConfig::default(),
+            severity_overrides: Vec::new(),
+            filters: FilterConfig::default(),
         }
     }
 
@@ -115,23 +340,82 @@ impl Config {
         if other.defaults.output.is_some() {
             self.defaults.output = other.defaults.output;
         }
+        if other.defaults.save_raw.is_some() {
+            self.defaults.save_raw = other.defaults.save_raw;
+        }
+        if other.defaults.cache.is_some() {
+            self.defaults.cache = other.defaults.cache;
+        }
         for (name, tool) in other.tools {
             self.tools.insert(name, tool);
         }
+        self.report.merge(other.report);
+        let correlation = other.correlation;
+        if correlation.enabled.is_some() {
+            self.correlation.enabled = correlation.enabled;
+        }
+        if correlation.line_window.is_some() {
+            self.correlation.line_window = correlation.line_window;
+        }
+        if correlation.min_tools.is_some() {
+            self.correlation.min_tools = correlation.min_tools;
+        }
+        self.paths.remap.extend(other.paths.remap);
+        self.severity_overrides.extend(other.severity_overrides);
+        let filters = other.filters;
+        if filters.min_severity.is_some() {
+            self.filters.min_severity = filters.min_severity;
+        }
+        self.filters.exclude_tools.extend(filters.exclude_tools);
+        self.filters.exclude_rules.extend(filters.exclude_rules);
+        self.filters.include_paths.extend(filters.include_paths);
+        self.filters.exclude_paths.extend(filters.exclude_paths);
     }
 }
 
-pub fn load_config(cli_config_path: Option<&Path>) -> Result<Config> {
+/// Accept either a single string (`tools: auto`, `format: html`) or a list of strings.
+fn one_or_many<'de, D>(deserializer: D) -> std::result::Result<Option<Vec<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(
+        match <Option<OneOrMany> as serde::Deserialize>::deserialize(deserializer)? {
+            Some(OneOrMany::One(s)) => Some(vec![s]),
+            Some(OneOrMany::Many(v)) => Some(v),
+            None => None,
+        },
+    )
+}
+
+/// Merge the config files over the built-in defaults. A scanned project's
+/// own `sast.yaml` (as written by `sast init`) is loaded after the one in
+/// the current directory.
+pub fn load_config(cli_config_path: Option<&Path>, project_path: Option<&Path>) -> Result<Config> {
     let mut config = Config::builtin_defaults();
 
     let candidates: Vec<Option<PathBuf>> = vec![
         dirs::config_dir().map(|d| d.join("sast/config.yaml")),
         Some(PathBuf::from("sast.yaml")),
+        project_path.map(|p| p.join("sast.yaml")),
         cli_config_path.map(PathBuf::from),
     ];
 
+    let mut loaded = Vec::new();
     for path in candidates.into_iter().flatten() {
+        // `sast .` would otherwise load ./sast.yaml twice
+        let canonical = std::fs::canonicalize(&path).ok();
+        if canonical.is_some() && loaded.contains(&canonical) {
+            continue;
+        }
         if path.exists() {
+            loaded.push(canonical);
             let text = std::fs::read_to_string(&path)?;
             let layer: Config = serde_yaml::from_str(&text)?;
             tracing::info!("Loaded config from {}", path.display());
@@ -0,0 +1,148 @@
+//! Cross-tool correlation: findings from different tools about the same
+//! weakness at the same place are reported once.
+
+use crate::model::{Correlated, Finding};
+use crate::output::display_path;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Lines two findings may be apart and still be about the same code
+pub const DEFAULT_LINE_WINDOW: u32 = 2;
+
+/// CWEs tools use interchangeably for one weakness. A CWE outside every
+/// class only matches itself.
+const CWE_CLASSES: &[&[u32]] = &[
+    // Out-of-bounds access and unbounded copies
+    &[119, 120, 121, 122, 125, 787, 788],
+    // Calls to dangerous or obsolete functions such as gets()
+    &[242, 477, 676],
+    // Uninitialized reads
+    &[457, 908, 909],
+    // Use after free
+    &[416, 825],
+    // NULL dereference and unchecked allocation results
+    &[476, 690],
+    // Memory and resource leaks
+    &[401, 772, 775],
+    // Format strings
+    &[134],
+    // Double free
+    &[415],
+];
+
+pub struct Options {
+    /// Merge correlated findings; when off findings pass through unchanged
+    pub enabled: bool,
+    pub line_window: u32,
+    /// Keep only groups reported by at least this many tools
+    pub min_tools: usize,
+}
+
+/// Group equivalent findings and report each group once: the most severe
+/// finding, with the others attached as `correlated`.
+pub fn correlate(findings: Vec<Finding>, project_path: &Path, options: &Options) -> Vec<Finding> {
+    if !options.enabled {
+        return findings;
+    }
+
+    let paths: Vec<String> = findings
+        .iter()
+        .map(|f| display_path(&f.location.file, project_path))
+        .collect();
+    let mut by_file: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (i, path) in paths.iter().enumerate() {
+        by_file.entry(path).or_default().push(i);
+    }
+
+    // Union-find over findings in the same file
+    let mut parent: Vec<usize> = (0..findings.len()).collect();
+    for indices in by_file.values() {
+        for (n, &a) in indices.iter().enumerate() {
+            for &b in &indices[n + 1..] {
+                if equivalent(&findings[a], &findings[b], options.line_window) {
+                    let (ra, rb) = (root(&mut parent, a), root(&mut parent, b));
+                    parent[ra.max(rb)] = ra.min(rb);
+                }
+            }
+        }
+    }
+
+    // Groups keep the order of their first finding
+    let mut groups: BTreeMap<usize, Vec<Finding>> = BTreeMap::new();
+    for (i, f) in findings.into_iter().enumerate() {
+        let r = root(&mut parent, i);
+        groups.entry(r).or_default().push(f);
+    }
+
+    groups
+        .into_values()
+        .map(merge)
+        .filter(|f| f.tools().len() >= options.min_tools)
+        .collect()
+}
+
+fn root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// Same tool and rule on the same line, or different tools flagging the
+/// same class of weakness within `window` lines.
+fn equivalent(a: &Finding, b: &Finding, window: u32) -> bool {
+    let (Some(line_a), Some(line_b)) = (a.location.line, b.location.line) else {
+        return false;
+    };
+    if a.tool == b.tool {
+        return a.rule_id.is_some() && a.rule_id == b.rule_id && line_a == line_b;
+    }
+    if line_a.abs_diff(line_b) > window {
+        return false;
+    }
+    let classes_b: Vec<usize> = b.cwe.iter().map(|c| class(*c)).collect();
+    a.cwe.iter().any(|c| classes_b.contains(&class(*c)))
+}
+
+/// Index of the CWE's class, or a value unique to the CWE.
+fn class(cwe: u32) -> usize {
+    CWE_CLASSES
+        .iter()
+        .position(|class| class.contains(&cwe))
+        .unwrap_or(CWE_CLASSES.len() + cwe as usize)
+}
+
+fn merge(mut group: Vec<Finding>) -> Finding {
+    // Most severe first; ties go to the tool with the most detail
+    group.sort_by_key(|f| {
+        (
+            std::cmp::Reverse(f.severity.rank()),
+            f.location.end_column.is_none(),
+            f.tool.clone(),
+        )
+    });
+    let mut group = group.into_iter();
+    let mut primary = group.next().expect("groups are never empty");
+    for other in group {
+        for cwe in &other.cwe {
+            if !primary.cwe.contains(cwe) {
+                primary.cwe.push(*cwe);
+            }
+        }
+        for owasp in &other.owasp {
+            if !primary.owasp.contains(owasp) {
+                primary.owasp.push(owasp.clone());
+            }
+        }
+        primary.correlated.push(Correlated {
+            tool: other.tool,
+            rule_id: other.rule_id,
+            severity: other.severity,
+            message: other.message,
+            location: other.location,
+            fingerprint: other.fingerprint,
+        });
+    }
+    primary
+}
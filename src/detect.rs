@@ -0,0 +1,239 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Directories that hold vendored code, dependencies or build output. They
+/// are not descended into and are reported so configs can exclude them.
+const SKIP_DIRS: &[&str] = &[
+    "third_party",
+    "third-party",
+    "thirdparty",
+    "vendor",
+    "external",
+    "extern",
+    "node_modules",
+    "build",
+    "out",
+    "dist",
+    "target",
+    "venv",
+    "sast_report",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Language {
+    C,
+    Cpp,
+    CSharp,
+    Go,
+    Java,
+    JavaScript,
+    Kotlin,
+    Php,
+    Python,
+    Ruby,
+    Rust,
+    Swift,
+    TypeScript,
+}
+
+impl Language {
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
+            "c" | "h" => Some(Self::C),
+            "cpp" | "cc" | "cxx" | "c++" | "hpp" | "hh" | "hxx" => Some(Self::Cpp),
+            "cs" => Some(Self::CSharp),
+            "go" => Some(Self::Go),
+            "java" => Some(Self::Java),
+            "js" | "jsx" | "mjs" | "cjs" => Some(Self::JavaScript),
+            "kt" | "kts" => Some(Self::Kotlin),
+            "php" => Some(Self::Php),
+            "py" => Some(Self::Python),
+            "rb" => Some(Self::Ruby),
+            "rs" => Some(Self::Rust),
+            "swift" => Some(Self::Swift),
+            "ts" | "tsx" => Some(Self::TypeScript),
+            _ => None,
+        }
+    }
+
+    /// Lowercase identifier, matching the language names semgrep uses.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::C => "c",
+            Self::Cpp => "cpp",
+            Self::CSharp => "csharp",
+            Self::Go => "go",
+            Self::Java => "java",
+            Self::JavaScript => "javascript",
+            Self::Kotlin => "kotlin",
+            Self::Php => "php",
+            Self::Python => "python",
+            Self::Ruby => "ruby",
+            Self::Rust => "rust",
+            Self::Swift => "swift",
+            Self::TypeScript => "typescript",
+        }
+    }
+}
+
+impl std::fmt::Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// What `scan_project` learned about a source tree.
+#[derive(Debug, Default)]
+pub struct ProjectInfo {
+    /// Number of source files per language
+    pub languages: BTreeMap<Language, usize>,
+    /// Number of source files per extension, used to pick source globs
+    pub extensions: BTreeMap<String, usize>,
+    /// Build systems found at the project root
+    pub build_systems: Vec<&'static str>,
+    /// compile_commands.json, relative to the project root
+    pub compile_database: Option<PathBuf>,
+    /// Vendored or generated directories that were skipped, relative to the root
+    pub skipped_dirs: Vec<PathBuf>,
+    /// Whether source files exist below the root directory
+    pub nested_sources: bool,
+}
+
+pub fn scan_project(root: &Path) -> Result<ProjectInfo> {
+    let mut info = ProjectInfo::default();
+
+    for file in walk_files(root, &mut info.skipped_dirs)? {
+        let Some(ext) = file.extension().map(|e| e.to_string_lossy().to_lowercase()) else {
+            continue;
+        };
+        if let Some(lang) = Language::from_extension(&ext) {
+            *info.languages.entry(lang).or_default() += 1;
+            *info.extensions.entry(ext).or_default() += 1;
+            if file.parent() != Some(root) {
+                info.nested_sources = true;
+            }
+        }
+    }
+
+    info.build_systems = detect_build_systems(root);
+    info.compile_database = find_compile_database(root);
+
+    for dir in &mut info.skipped_dirs {
+        if let Ok(rel) = dir.strip_prefix(root) {
+            *dir = rel.to_path_buf();
+        }
+    }
+
+    Ok(info)
+}
+
+/// Recursively list files under `root`, skipping hidden and vendored directories.
+pub fn walk_files(root: &Path, skipped: &mut Vec<PathBuf>) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+
+    while let Some(dir) = stack.pop() {
+        let mut entries: Vec<_> = std::fs::read_dir(&dir)?.flatten().collect();
+        entries.sort_by_key(|e| e.file_name());
+
+        for entry in entries {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+
+            if file_type.is_dir() {
+                if name.starts_with('.') {
+                    continue;
+                }
+                if SKIP_DIRS.contains(&name.as_str()) || name.starts_with("cmake-build-") {
+                    skipped.push(path);
+                    continue;
+                }
+                stack.push(path);
+            } else if file_type.is_file() {
+                files.push(path);
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+fn detect_build_systems(root: &Path) -> Vec<&'static str> {
+    let markers: &[(&str, &str)] = &[
+        ("CMakeLists.txt", "CMake"),
+        ("meson.build", "Meson"),
+        ("configure.ac", "Autotools"),
+        ("Makefile", "Make"),
+        ("WORKSPACE", "Bazel"),
+        ("MODULE.bazel", "Bazel"),
+        ("Cargo.toml", "Cargo"),
+        ("go.mod", "Go modules"),
+        ("package.json", "npm"),
+        ("pyproject.toml", "Python (pyproject)"),
+        ("setup.py", "Python (setuptools)"),
+        ("pom.xml", "Maven"),
+        ("build.gradle", "Gradle"),
+        ("build.gradle.kts", "Gradle"),
+    ];
+
+    let mut found = Vec::new();
+    for (file, system) in markers {
+        if root.join(file).exists() && !found.contains(system) {
+            found.push(*system);
+        }
+    }
+    found
+}
+
+fn find_compile_database(root: &Path) -> Option<PathBuf> {
+    let direct = root.join("compile_commands.json");
+    if direct.exists() {
+        return Some(PathBuf::from("compile_commands.json"));
+    }
+
+    // CMake and Meson put it in the build directory
+    let mut dirs: Vec<_> = std::fs::read_dir(root)
+        .ok()?
+        .flatten()
+        .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|n| n.starts_with("build") || n.starts_with("cmake-build-") || n == "out")
+        .collect();
+    dirs.sort();
+
+    dirs.into_iter()
+        .map(|d| PathBuf::from(d).join("compile_commands.json"))
+        .find(|p| root.join(p).exists())
+}
+
+/// Locate an executable on PATH, like `which`.
+pub fn find_executable(command: &str) -> Option<PathBuf> {
+    let candidate = Path::new(command);
+    if candidate.components().count() > 1 {
+        return candidate.is_file().then(|| candidate.to_path_buf());
+    }
+
+    let extensions: &[&str] = if cfg!(windows) {
+        &["exe", "bat", "cmd"]
+    } else {
+        &[""]
+    };
+
+    let path_var = std::env::var_os("PATH")?;
+    for dir in std::env::split_paths(&path_var) {
+        for ext in extensions {
+            let mut path = dir.join(command);
+            if !ext.is_empty() {
+                path.set_extension(ext);
+            }
+            if path.is_file() {
+                return Some(path);
+            }
+        }
+    }
+    None
+}
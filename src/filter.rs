@@ -0,0 +1,145 @@
+//! Report-time filters: findings nobody wants to see, such as cppcheck's
+//! `missingIncludeSystem` or anything under `third_party/`, dropped before
+//! any writer runs.
+
+use crate::config::FilterConfig;
+use crate::model::{Finding, Severity};
+use crate::output::display_path;
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// How many findings one filter removed; recorded in the report metadata.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FilterCount {
+    /// The filter's option name, e.g. `exclude-rule`
+    pub filter: String,
+    pub removed: usize,
+}
+
+enum Filter {
+    ExcludeTool(Vec<String>),
+    ExcludeRule(Vec<glob::Pattern>),
+    IncludePath(Vec<glob::Pattern>),
+    ExcludePath(Vec<glob::Pattern>),
+    MinSeverity(Severity),
+}
+
+impl Filter {
+    fn name(&self) -> &'static str {
+        match self {
+            Filter::ExcludeTool(_) => "exclude-tool",
+            Filter::ExcludeRule(_) => "exclude-rule",
+            Filter::IncludePath(_) => "include-path",
+            Filter::ExcludePath(_) => "exclude-path",
+            Filter::MinSeverity(_) => "min-severity",
+        }
+    }
+
+    /// Whether `f`, at the project-relative `path`, stays in the report.
+    fn keeps(&self, f: &Finding, path: &str) -> bool {
+        match self {
+            Filter::ExcludeTool(tools) => !tools.contains(&f.tool),
+            Filter::ExcludeRule(rules) => !f
+                .rule_id
+                .as_deref()
+                .is_some_and(|rule| rules.iter().any(|p| p.matches(rule))),
+            // Findings about the whole project (the generic parser's) have
+            // no file for a path filter to match
+            Filter::IncludePath(_) | Filter::ExcludePath(_) if path == "." => true,
+            Filter::IncludePath(paths) => paths.iter().any(|p| matches_path(p, path)),
+            Filter::ExcludePath(paths) => !paths.iter().any(|p| matches_path(p, path)),
+            Filter::MinSeverity(min) => f.severity.rank() >= min.rank(),
+        }
+    }
+}
+
+/// Drop the findings `config` excludes. Each removed finding is counted
+/// against the first filter that rejected it; every configured filter is
+/// listed, even when it removed nothing.
+pub fn apply(
+    findings: Vec<Finding>,
+    config: &FilterConfig,
+    project_path: &Path,
+) -> Result<(Vec<Finding>, Vec<FilterCount>)> {
+    let mut filters = Vec::new();
+    if !config.exclude_tools.is_empty() {
+        filters.push(Filter::ExcludeTool(config.exclude_tools.clone()));
+    }
+    if !config.exclude_rules.is_empty() {
+        filters.push(Filter::ExcludeRule(patterns(&config.exclude_rules)?));
+    }
+    if !config.include_paths.is_empty() {
+        filters.push(Filter::IncludePath(patterns(&config.include_paths)?));
+    }
+    if !config.exclude_paths.is_empty() {
+        filters.push(Filter::ExcludePath(patterns(&config.exclude_paths)?));
+    }
+    if let Some(min) = config.min_severity {
+        filters.push(Filter::MinSeverity(min));
+    }
+
+    let mut removed = vec![0; filters.len()];
+    let kept = findings
+        .into_iter()
+        .filter(|f| {
+            let path = display_path(&f.location.file, project_path);
+            match filters.iter().position(|filter| !filter.keeps(f, &path)) {
+                Some(i) => {
+                    removed[i] += 1;
+                    false
+                }
+                None => true,
+            }
+        })
+        .collect();
+    let counts = filters
+        .iter()
+        .zip(removed)
+        .map(|(filter, removed)| FilterCount {
+            filter: filter.name().to_string(),
+            removed,
+        })
+        .collect();
+    Ok((kept, counts))
+}
+
+/// The `include_paths` and `exclude_paths` filters on their own. Tools with
+/// `append_sources` are only handed the files these keep.
+#[derive(Default)]
+pub struct PathFilter {
+    include: Vec<glob::Pattern>,
+    exclude: Vec<glob::Pattern>,
+}
+
+impl PathFilter {
+    pub fn new(config: &FilterConfig) -> Result<Self> {
+        Ok(Self {
+            include: patterns(&config.include_paths)?,
+            exclude: patterns(&config.exclude_paths)?,
+        })
+    }
+
+    /// Whether the project-relative `path` passes both filters.
+    pub fn keeps(&self, path: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| matches_path(p, path)))
+            && !self.exclude.iter().any(|p| matches_path(p, path))
+    }
+}
+
+fn patterns(globs: &[String]) -> Result<Vec<glob::Pattern>> {
+    globs
+        .iter()
+        // `third_party/` means the directory
+        .map(|g| g.trim_end_matches('/'))
+        .map(|g| glob::Pattern::new(g).with_context(|| format!("Invalid filter pattern '{g}'")))
+        .collect()
+}
+
+/// A path pattern matches the file or any directory containing it, so
+/// `third_party` covers `third_party/zlib/inflate.c`.
+fn matches_path(pattern: &glob::Pattern, path: &str) -> bool {
+    Path::new(path)
+        .ancestors()
+        .filter(|p| !p.as_os_str().is_empty())
+        .any(|p| pattern.matches(&p.to_string_lossy()))
+}
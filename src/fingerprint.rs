@@ -0,0 +1,94 @@
+//! Stable identity of a finding across scans, for tracking, deduplication
+//! and baselines.
+//!
+//! The hash covers what stays the same when unrelated code moves: the tool,
+//! the rule, the project-relative path, the flagged code with whitespace
+//! normalized and the message with volatile parts (identifiers, numbers)
+//! masked. Line and column numbers are left out so findings survive edits
+//! above them.
+
+use crate::model::Finding;
+use crate::output::{display_path, SourceCache};
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// Key of the fingerprint in SARIF `partialFingerprints`; bumped whenever
+/// the hashed parts change.
+pub const SARIF_KEY: &str = "sast/v2";
+
+/// Flagged ranges longer than this only contribute their first lines
+const MAX_SNIPPET_LINES: u32 = 5;
+
+/// Quoted names, trailing `: name` subjects and numbers, in that order.
+static VOLATILE: LazyLock<[(Regex, &str); 4]> = LazyLock::new(|| {
+    [
+        (Regex::new(r"'[^']*'").unwrap(), "'_'"),
+        (Regex::new(r#""[^"]*""#).unwrap(), "\"_\""),
+        (Regex::new(r":\s*[\w.\->\[\]]+\s*$").unwrap(), ": _"),
+        (Regex::new(r"\d+").unwrap(), "0"),
+    ]
+});
+
+/// Set `fingerprint` on every finding. Sources are read from
+/// `project_path` when the tool didn't print the flagged code.
+pub fn assign(findings: &mut [Finding], project_path: &Path) {
+    let mut sources = SourceCache::new(project_path);
+    for f in findings.iter_mut() {
+        let path = display_path(&f.location.file, project_path);
+        let code = code(f, &mut sources);
+        f.fingerprint = compute(f, &path, code.as_deref());
+    }
+}
+
+/// Fingerprint of `f` at the project-relative `path`, over the flagged
+/// `code` when known.
+pub fn compute(f: &Finding, path: &str, code: Option<&str>) -> String {
+    let code = code.map(normalize_code).unwrap_or_default();
+    let message = normalize_message(&f.message);
+    let mut hasher = Sha256::new();
+    for part in [
+        f.tool.as_str(),
+        f.rule_id.as_deref().unwrap_or(""),
+        path,
+        &code,
+        &message,
+    ] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// The flagged code: the tool's snippet, or the reported lines of the file.
+fn code(f: &Finding, sources: &mut SourceCache) -> Option<String> {
+    if let Some(snippet) = &f.snippet {
+        return Some(snippet.clone());
+    }
+    let start = f.location.line.filter(|l| *l > 0)?;
+    let end = f
+        .location
+        .end_line
+        .unwrap_or(start)
+        .clamp(start, start + MAX_SNIPPET_LINES - 1);
+    let lines = sources.lines(&f.location.file)?;
+    let code: Vec<&str> = (start..=end)
+        .filter_map(|l| lines.get(l as usize - 1).map(String::as_str))
+        .collect();
+    Some(code.join("\n"))
+}
+
+fn normalize_code(code: &str) -> String {
+    code.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// `Memory pointed to by 'arr' is freed twice.` and the same message about
+/// `'buf'` normalize alike.
+fn normalize_message(message: &str) -> String {
+    let mut message = message.trim().to_string();
+    for (re, replacement) in VOLATILE.iter() {
+        message = re.replace_all(&message, *replacement).into_owned();
+    }
+    normalize_code(&message)
+}
@@ -0,0 +1,291 @@
+use crate::config::{Config, OutputStream, ToolConfig};
+use crate::detect::{self, Language, ProjectInfo};
+use anyhow::{ensure, Result};
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Optional analyzers added to the starter config when the language is present
+/// and the tool is installed. Their output goes through the generic parser.
+const EXTRA_TOOLS: &[(&[Language], &str, &[&str])] = &[
+    (&[Language::Python], "bandit", &["-r", "-f", "txt"]),
+    (&[Language::C, Language::Cpp], "flawfinder", &["--columns"]),
+];
+
+pub fn run(path: &Path, force: bool) -> Result<()> {
+    ensure!(
+        path.is_dir(),
+        "Project path '{}' is not a directory",
+        path.display()
+    );
+
+    let config_path = path.join("sast.yaml");
+    ensure!(
+        force || !config_path.exists(),
+        "'{}' already exists (use --force to overwrite)",
+        config_path.display()
+    );
+
+    let info = detect::scan_project(path)?;
+    let yaml = render(&info, path);
+    std::fs::write(&config_path, yaml)?;
+
+    eprintln!("[sast] Detected languages: {}", describe_languages(&info));
+    eprintln!("[sast] Config written to {}", config_path.display());
+    eprintln!(
+        "[sast] Scans of {} load it; elsewhere pass `-c {}`",
+        path.display(),
+        config_path.display()
+    );
+    Ok(())
+}
+
+fn describe_languages(info: &ProjectInfo) -> String {
+    if info.languages.is_empty() {
+        return "none".to_string();
+    }
+    info.languages
+        .iter()
+        .map(|(lang, count)| format!("{lang} ({count} files)"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Build the tool entries suited to the detected languages, in run order.
+fn select_tools(info: &ProjectInfo) -> Vec<(String, ToolConfig)> {
+    let builtin = Config::builtin_defaults().tools;
+    let has_c = info.languages.contains_key(&Language::C);
+    let has_cpp = info.languages.contains_key(&Language::Cpp);
+    let mut tools = Vec::new();
+
+    let exclude_args = |flag: &str, prefix: &str| -> Vec<String> {
+        info.skipped_dirs
+            .iter()
+            .map(|d| format!("{flag}{prefix}{}", d.to_string_lossy().replace('\\', "/")))
+            .collect()
+    };
+
+    if has_c || has_cpp {
+        let mut cppcheck = builtin["cppcheck"].clone();
+        if !has_cpp {
+            cppcheck.args.retain(|a| !a.starts_with("--std="));
+            cppcheck.args.push("--std=c11".into());
+        }
+        cppcheck.args.extend(exclude_args("-i", "{project_path}/"));
+        tools.push(("cppcheck".to_string(), cppcheck));
+
+        let mut scan_build = builtin["scan-build"].clone();
+        let (lang, compiler, std) = if has_cpp {
+            (Language::Cpp, "clang++", "-std=c++17")
+        } else {
+            (Language::C, "clang", "-std=c11")
+        };
+        scan_build.languages = vec![lang.name().to_string()];
+        for arg in &mut scan_build.args {
+            if arg == "clang++" {
+                *arg = compiler.to_string();
+            } else if arg.starts_with("-std=") {
+                *arg = std.to_string();
+            }
+        }
+        scan_build.append_sources = Some(source_glob(info, lang));
+        tools.push(("scan-build".to_string(), scan_build));
+    }
+
+    if !info.languages.is_empty() {
+        let mut semgrep = builtin["semgrep"].clone();
+        if !(has_c || has_cpp) {
+            // The bundled rules only cover C and C++
+            semgrep.args.retain(|a| a != "--config=rules/semgrep/");
+        }
+        semgrep.args.extend(exclude_args("--exclude=", ""));
+        tools.push(("semgrep".to_string(), semgrep));
+    }
+
+    for (langs, name, args) in EXTRA_TOOLS {
+        let relevant = langs.iter().any(|l| info.languages.contains_key(l));
+        if relevant && detect::find_executable(name).is_some() {
+            tools.push((
+                name.to_string(),
+                ToolConfig {
+                    command: name.to_string(),
+                    args: args.iter().map(|a| a.to_string()).collect(),
+                    output_stream: OutputStream::Stdout,
+                    native_extension: Some("txt".into()),
+                    append_sources: None,
+                    languages: langs.iter().map(|l| l.name().to_string()).collect(),
+                    files: Vec::new(),
+                    severity_map: Default::default(),
+                    cache_per_file: false,
+                },
+            ));
+        }
+    }
+
+    tools
+}
+
+/// Glob for the most common source extension of `lang`, recursive when sources
+/// live below the project root.
+fn source_glob(info: &ProjectInfo, lang: Language) -> String {
+    let ext = info
+        .extensions
+        .iter()
+        .filter(|(ext, _)| Language::from_extension(ext) == Some(lang))
+        .filter(|(ext, _)| !matches!(ext.as_str(), "h" | "hpp" | "hh" | "hxx"))
+        .max_by_key(|(_, count)| **count)
+        .map(|(ext, _)| ext.as_str())
+        .unwrap_or(if lang == Language::Cpp { "cpp" } else { "c" });
+
+    if info.nested_sources {
+        format!("**/*.{ext}")
+    } else {
+        format!("*.{ext}")
+    }
+}
+
+fn render(info: &ProjectInfo, root: &Path) -> String {
+    let tools = select_tools(info);
+    let installed: Vec<&str> = tools
+        .iter()
+        .filter(|(_, t)| detect::find_executable(&t.command).is_some())
+        .map(|(name, _)| name.as_str())
+        .collect();
+
+    // Prefer SARIF when the project already runs GitHub workflows (code scanning)
+    let format = if root.join(".github/workflows").is_dir() {
+        "sarif"
+    } else {
+        "html"
+    };
+
+    let mut out = String::new();
+    let _ = writeln!(out, "# sast configuration generated by `sast init`");
+    let _ = writeln!(out, "#");
+    let _ = writeln!(out, "# Languages:        {}", describe_languages(info));
+    let _ = writeln!(
+        out,
+        "# Build systems:    {}",
+        if info.build_systems.is_empty() {
+            "none detected".to_string()
+        } else {
+            info.build_systems.join(", ")
+        }
+    );
+    match &info.compile_database {
+        Some(db) => {
+            let _ = writeln!(
+                out,
+                "# Compile database: {} (cppcheck can use it via --project=)",
+                db.display()
+            );
+        }
+        None => {
+            let _ = writeln!(out, "# Compile database: none");
+        }
+    }
+    if !info.skipped_dirs.is_empty() {
+        let dirs: Vec<_> = info
+            .skipped_dirs
+            .iter()
+            .map(|d| d.to_string_lossy().to_string())
+            .collect();
+        let _ = writeln!(out, "# Excluded:         {}", dirs.join(", "));
+    }
+    let _ = writeln!(out, "#");
+    let _ = writeln!(out, "# Analyzers:");
+    for (name, tool) in &tools {
+        let status = match detect::find_executable(&tool.command) {
+            Some(path) => format!("installed ({})", path.display()),
+            None => "not found on PATH".to_string(),
+        };
+        let _ = writeln!(out, "#   {name:<12} {status}");
+    }
+    let _ = writeln!(out);
+
+    let _ = writeln!(out, "defaults:");
+    let _ = writeln!(
+        out,
+        "  # Tools run when -t is not given (`auto` selects them by language)"
+    );
+    if installed.is_empty() {
+        let names: Vec<&str> = tools.iter().map(|(n, _)| n.as_str()).collect();
+        let _ = writeln!(out, "  # None of the suggested analyzers are installed yet");
+        let _ = writeln!(out, "  tools: {}", yaml_list(&names));
+    } else {
+        let _ = writeln!(out, "  tools: {}", yaml_list(&installed));
+    }
+    let _ = writeln!(
+        out,
+        "  # Output format, or a list of them: native, sarif, html, json, markdown,\n  # gitlab-sast, gitlab-codequality, junit, checkstyle, sonar, csv, jsonl, pretty, template"
+    );
+    let _ = writeln!(out, "  format: {format}");
+    let _ = writeln!(out);
+
+    let include: Vec<String> = info
+        .extensions
+        .keys()
+        .map(|ext| format!("*.{ext}"))
+        .collect();
+    let exclude: Vec<String> = info
+        .skipped_dirs
+        .iter()
+        .map(|d| d.to_string_lossy().replace('\\', "/"))
+        .collect();
+    if !include.is_empty() || !exclude.is_empty() {
+        let _ = writeln!(
+            out,
+            "# Paths reports keep or leave out; tools given a file list (append_sources)\n# only get the files kept"
+        );
+        let _ = writeln!(out, "filters:");
+        if !include.is_empty() {
+            let _ = writeln!(out, "  include_paths: {}", yaml_list(&include));
+        }
+        if !exclude.is_empty() {
+            let _ = writeln!(out, "  exclude_paths: {}", yaml_list(&exclude));
+        }
+        let _ = writeln!(out);
+    }
+
+    let _ = writeln!(out, "tools:");
+    for (name, tool) in &tools {
+        let _ = writeln!(out, "  {name}:");
+        let _ = writeln!(out, "    command: {}", yaml_str(&tool.command));
+        let _ = writeln!(out, "    args: {}", yaml_list(&tool.args));
+        let stream = match tool.output_stream {
+            OutputStream::Stdout => "stdout",
+            OutputStream::Stderr => "stderr",
+            OutputStream::Filesystem => "filesystem",
+        };
+        let _ = writeln!(out, "    output_stream: {stream}");
+        if let Some(ext) = &tool.native_extension {
+            let _ = writeln!(out, "    native_extension: {ext}");
+        }
+        if !tool.languages.is_empty() {
+            let _ = writeln!(out, "    languages: {}", yaml_list(&tool.languages));
+        }
+        if let Some(pattern) = &tool.append_sources {
+            let _ = writeln!(out, "    # Source files passed to the compiler");
+            let _ = writeln!(out, "    append_sources: {}", yaml_str(pattern));
+        }
+        let _ = writeln!(out);
+    }
+
+    out
+}
+
+fn yaml_str(s: &str) -> String {
+    let plain = s.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-.+/".contains(c));
+    if plain {
+        s.to_string()
+    } else {
+        // JSON strings are valid YAML flow scalars
+        serde_json::to_string(s).unwrap_or_default()
+    }
+}
+
+fn yaml_list<S: AsRef<str>>(items: &[S]) -> String {
+    let quoted: Vec<String> = items.iter().map(|s| yaml_str(s.as_ref())).collect();
+    format!("[{}]", quoted.join(", "))
+}
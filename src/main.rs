@@ -1,45 +1,363 @@
+mod baseline;
+mod cache;
+mod changes;
+mod classify;
 mod cli;
 mod config;
+mod correlate;
+mod detect;
+mod filter;
+mod fingerprint;
+mod init;
+mod metadata;
 mod model;
 mod output;
+mod paths;
+mod replay;
 mod runner;
+mod severity;
 mod tools;
 
-use anyhow::{ensure, Result};
+use anyhow::{ensure, Context, Result};
 use clap::Parser;
 use output::OutputFormat;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt::init();
+    // Logs go to stderr with the progress output; stdout may carry a report
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .init();
     let cli = cli::Cli::parse();
 
+    match cli.command {
+        Some(cli::Command::Init { ref path, force }) => init::run(path, force),
+        Some(cli::Command::Replay {
+            dir,
+            format,
+            output,
+            stdout,
+            config,
+            correlation,
+            filters,
+        }) => {
+            replay(
+                &dir,
+                format,
+                output,
+                stdout,
+                config.as_deref(),
+                &correlation,
+                &filters,
+            )
+            .await
+        }
+        Some(cli::Command::Diff {
+            old,
+            new,
+            format,
+            output,
+            config,
+            new_only,
+            fail_on_new,
+        }) => {
+            diff(
+                &old,
+                &new,
+                format,
+                output,
+                config.as_deref(),
+                new_only,
+                fail_on_new,
+            )
+            .await
+        }
+        Some(cli::Command::Cache {
+            action: cli::CacheCommand::Clean,
+        }) => clean_cache(),
+        None => scan(cli).await,
+    }
+}
+
+fn clean_cache() -> Result<()> {
+    let dir = cache::dir().context("No cache directory on this system")?;
+    let removed = cache::clean(&dir)?;
+    eprintln!(
+        "[sast] Removed {removed} cached result(s) from {}",
+        dir.display()
+    );
+    Ok(())
+}
+
+fn resolve_formats(
+    cli_formats: Option<Vec<String>>,
+    config: &config::Config,
+) -> Result<Vec<OutputFormat>> {
+    let format_names = cli_formats
+        .or(config.defaults.format.clone())
+        .unwrap_or_else(|| vec!["native".to_string()]);
+    OutputFormat::parse_list(&format_names)
+}
+
+/// `--stdout` or `-o -`: the report goes to stdout, progress stays on stderr.
+fn split_stdout(output: Option<PathBuf>, stdout: bool) -> (Option<PathBuf>, bool) {
+    match output {
+        Some(path) if path.as_os_str() == "-" => (None, true),
+        output => (output, stdout),
+    }
+}
+
+/// The configured filters plus those given on the command line.
+fn effective_filters(config: &config::Config, args: &cli::FilterArgs) -> config::FilterConfig {
+    let mut filters = config.filters.clone();
+    if args.min_severity.is_some() {
+        filters.min_severity = args.min_severity;
+    }
+    filters
+        .exclude_tools
+        .extend(args.exclude_tool.iter().cloned());
+    filters
+        .exclude_rules
+        .extend(args.exclude_rule.iter().cloned());
+    filters
+        .include_paths
+        .extend(args.include_path.iter().cloned());
+    filters
+        .exclude_paths
+        .extend(args.exclude_path.iter().cloned());
+    filters
+}
+
+/// Parse the runs into the findings every report is built from. What the
+/// filters removed is recorded in `metadata`. Incremental scans pass the
+/// `changed` files and whether to keep only findings on changed lines.
+fn collect_findings(
+    runs: &[runner::ToolRun],
+    project_path: &Path,
+    config: &config::Config,
+    args: &cli::CorrelationArgs,
+    filter_args: &cli::FilterArgs,
+    changed: Option<(&changes::ChangeSet, bool)>,
+    metadata: &mut metadata::ScanMetadata,
+) -> Result<Vec<model::Finding>> {
+    let mut collector = Collector::new(project_path, config, args, filter_args, changed)?;
+    collector.add(runs)?;
+    Ok(collector.finish(metadata))
+}
+
+/// [`collect_findings`] one run at a time, for scans that take each tool's
+/// findings as soon as it finishes. Findings are compared with each other
+/// only once all runs are in.
+struct Collector<'a> {
+    project_path: &'a Path,
+    config: &'a config::Config,
+    filters: config::FilterConfig,
+    changed: Option<(&'a changes::ChangeSet, bool)>,
+    correlation: correlate::Options,
+    normalizer: paths::Normalizer,
+    findings: Vec<model::Finding>,
+    filtered: Vec<filter::FilterCount>,
+    outside: BTreeSet<PathBuf>,
+}
+
+impl<'a> Collector<'a> {
+    fn new(
+        project_path: &'a Path,
+        config: &'a config::Config,
+        args: &cli::CorrelationArgs,
+        filter_args: &cli::FilterArgs,
+        changed: Option<(&'a changes::ChangeSet, bool)>,
+    ) -> Result<Self> {
+        let options = &config.correlation;
+        let enabled = !args.no_dedup && options.enabled.unwrap_or(true);
+        let min_tools = args.consensus.or(options.min_tools).unwrap_or(1);
+        ensure!(
+            enabled || min_tools <= 1,
+            "Consensus findings need deduplication; drop --no-dedup or `correlation.enabled: false`"
+        );
+        Ok(Self {
+            project_path,
+            config,
+            filters: effective_filters(config, filter_args),
+            changed,
+            correlation: correlate::Options {
+                enabled,
+                line_window: options
+                    .line_window
+                    .unwrap_or(correlate::DEFAULT_LINE_WINDOW),
+                min_tools,
+            },
+            normalizer: paths::Normalizer::new(project_path, &config.paths.remap),
+            findings: Vec::new(),
+            filtered: Vec::new(),
+            outside: BTreeSet::new(),
+        })
+    }
+
+    /// Whether the findings [`Self::add`] returns are final, i.e. no other
+    /// tool's findings are merged into them.
+    fn is_final(&self) -> bool {
+        !self.correlation.enabled
+    }
+
+    /// Parse `runs`, returning the findings they add.
+    fn add(&mut self, runs: &[runner::ToolRun]) -> Result<&[model::Finding]> {
+        let config = self.config;
+        let mut findings = tools::parse_runs(runs);
+        severity::apply(&mut findings, &config.tools, &config.severity_overrides)?;
+        self.outside
+            .extend(self.normalizer.normalize(&mut findings));
+        fingerprint::assign(&mut findings, self.project_path);
+
+        let (mut findings, mut filtered) =
+            filter::apply(findings, &self.filters, self.project_path)?;
+        if let Some((changed, lines_only)) = self.changed {
+            let (kept, counts) = changed.filter(findings, lines_only);
+            findings = kept;
+            filtered.extend(counts);
+        }
+        // Every run goes through the same filters
+        if self.filtered.is_empty() {
+            self.filtered = filtered;
+        } else {
+            for (total, count) in self.filtered.iter_mut().zip(filtered) {
+                total.removed += count.removed;
+            }
+        }
+        if self.is_final() {
+            classify::assign(&mut findings);
+        }
+
+        let start = self.findings.len();
+        self.findings.extend(findings);
+        Ok(&self.findings[start..])
+    }
+
+    /// All findings, correlated. What the filters removed is recorded in
+    /// `metadata`.
+    fn finish(self, metadata: &mut metadata::ScanMetadata) -> Vec<model::Finding> {
+        if !self.outside.is_empty() {
+            let files: Vec<String> = self
+                .outside
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect();
+            eprintln!(
+                "[sast] Warning: findings in {} file(s) outside the project: {}",
+                files.len(),
+                files.join(", ")
+            );
+        }
+        let removed: usize = self.filtered.iter().map(|c| c.removed).sum();
+        if removed > 0 {
+            eprintln!("[sast] Filters: {removed} findings left out");
+        }
+        metadata.filtered = self.filtered;
+
+        let parsed = self.findings.len();
+        let mut findings =
+            correlate::correlate(self.findings, self.project_path, &self.correlation);
+        if findings.len() < parsed {
+            eprintln!(
+                "[sast] Correlation: {parsed} findings reported as {}",
+                findings.len()
+            );
+        }
+        classify::assign(&mut findings);
+        findings
+    }
+}
+
+/// Mark `findings` new or unchanged against the `earlier` findings of
+/// `report`, recording the outcome in `metadata`. Returns the findings to
+/// report and the fixed ones.
+fn compare_findings(
+    earlier: Vec<model::Finding>,
+    findings: Vec<model::Finding>,
+    report: &Path,
+    new_only: bool,
+    metadata: &mut metadata::ScanMetadata,
+) -> (Vec<model::Finding>, Vec<model::Finding>) {
+    let mut comparison = baseline::compare(earlier, findings, report);
+    let summary = &comparison.summary;
+    eprintln!(
+        "[sast] Baseline: {} new, {} fixed, {} unchanged",
+        summary.new, summary.fixed, summary.unchanged
+    );
+    if new_only {
+        metadata.filtered.push(comparison.new_only());
+    }
+    metadata.baseline = Some(comparison.summary);
+    (comparison.findings, comparison.fixed)
+}
+
+fn project_name(project_path: &Path) -> String {
+    project_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "project".to_string())
+}
+
+async fn scan(cli: cli::Cli) -> Result<()> {
+    let started_at = chrono::Utc::now();
+    let project_path = cli.path.clone().expect("clap requires a project path");
+
     // Validate project path
     ensure!(
-        cli.path.exists(),
+        project_path.exists(),
         "Project path '{}' does not exist",
-        cli.path.display()
+        project_path.display()
     );
 
     // Load and merge config
-    let config = config::load_config(cli.config.as_deref())?;
+    let config = config::load_config(cli.config.as_deref(), Some(&project_path))?;
 
     // Resolve effective settings (CLI overrides config)
-    let tools_to_run = cli
+    let requested_tools = cli
         .tools
         .or(config.defaults.tools.clone())
         .unwrap_or_else(|| vec!["cppcheck".into(), "scan-build".into(), "semgrep".into()]);
 
-    let format_str = cli
-        .format
-        .or(config.defaults.format.clone())
-        .unwrap_or_else(|| "native".to_string());
-    let format = OutputFormat::from_str(&format_str)?;
+    let formats = resolve_formats(cli.format, &config)?;
+    // Read the report to compare with up front, before tools spend any time
+    let earlier = match &cli.compare_to {
+        Some(report) => Some(baseline::load(report).await?.1),
+        None => None,
+    };
+    let needs_findings = formats.iter().any(|f| *f != OutputFormat::Native) || earlier.is_some();
 
-    let output_dir = cli
-        .output
+    let (output, to_stdout) = split_stdout(cli.output, cli.stdout);
+    if to_stdout {
+        output::check_stdout(&formats)?;
+    }
+    let report_dir = output
         .or(config.defaults.output.clone())
-        .unwrap_or_else(|| cli.path.join("sast_report"));
+        .unwrap_or_else(|| project_path.join("sast_report"));
+    // Tools still write their own files; keep them out of the way
+    let output_dir = if to_stdout {
+        std::env::temp_dir().join(format!("sast-{}", std::process::id()))
+    } else {
+        report_dir.clone()
+    };
+
+    // `auto` schedules only the tools that apply to this project
+    let (mut tools_to_run, mut skipped_tools) = if requested_tools == ["auto"] {
+        runner::select_tools(&config.tools, &project_path)?
+    } else {
+        (requested_tools, Vec::new())
+    };
+    ensure!(
+        !tools_to_run.is_empty(),
+        "No applicable tools for this project: {}",
+        skipped_tools
+            .iter()
+            .map(|s| format!("{} ({})", s.name, s.reason))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
 
     // Validate requested tools exist in config
     for t in &tools_to_run {
@@ -51,28 +369,159 @@ async fn main() -> Result<()> {
         );
     }
 
+    // Tools that take a file list get none the path filters leave out
+    let paths = Arc::new(filter::PathFilter::new(&effective_filters(
+        &config,
+        &cli.filters,
+    ))?);
+
+    // Incremental scans hand tools that take a file list only changed files
+    let changed = match &cli.changed_since {
+        Some(base) => {
+            let changed = changes::ChangeSet::since(&project_path, base).await?;
+            tools_to_run.retain(|t| {
+                match runner::source_files(&config.tools[t], &project_path, &paths, Some(&changed))
+                {
+                    Some(files) if files.is_empty() => {
+                        skipped_tools.push(runner::SkippedTool {
+                            name: t.clone(),
+                            reason: "no changed sources".into(),
+                        });
+                        false
+                    }
+                    _ => true,
+                }
+            });
+            ensure!(
+                !tools_to_run.is_empty(),
+                "No changed files to scan since '{base}'"
+            );
+            Some(Arc::new(changed))
+        }
+        None => None,
+    };
+
+    // Read the revision before tools or reports touch the tree
+    let mut metadata =
+        metadata::ScanMetadata::start(started_at, &project_path, &output_dir, &config).await;
+
     // Create output directory
     tokio::fs::create_dir_all(&output_dir).await?;
 
     eprintln!("===== SAST Analysis =====");
-    eprintln!("Project:  {}", cli.path.display());
+    eprintln!("Project:  {}", project_path.display());
     eprintln!("Tools:    {}", tools_to_run.join(", "));
-    eprintln!("Format:   {}", format_str);
-    eprintln!("Output:   {}", output_dir.display());
+    if let (Some(base), Some(changed)) = (&cli.changed_since, &changed) {
+        eprintln!("Changed:  {} file(s) since {base}", changed.file_count());
+    }
+    for skipped in &skipped_tools {
+        eprintln!("Skipped:  {} ({})", skipped.name, skipped.reason);
+    }
+    eprintln!(
+        "Format:   {}",
+        formats
+            .iter()
+            .map(|f| f.name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    if to_stdout {
+        eprintln!("Output:   stdout");
+    } else {
+        eprintln!("Output:   {}", output_dir.display());
+    }
     eprintln!();
 
-    // Run tools in parallel
-    let results = runner::run_tools(&tools_to_run, &config.tools, &cli.path, &output_dir).await;
+    // Results are reused while the tools, their config and the project's
+    // files are unchanged; hashing the files is skipped when no tool could
+    // use them
+    let any_cacheable = tools_to_run
+        .iter()
+        .any(|t| runner::cacheable(&config.tools[t]));
+    let cache = if cli.no_cache || !config.defaults.cache.unwrap_or(true) || !any_cacheable {
+        None
+    } else {
+        match cache::Cache::open(&project_path, &output_dir) {
+            Ok(cache) => cache.map(Arc::new),
+            Err(e) => {
+                eprintln!("[sast] Warning: not using the cache: {e:#}");
+                None
+            }
+        }
+    };
+    let options = runner::RunOptions {
+        changed: changed.clone(),
+        paths,
+        cache,
+    };
+
+    let project_name = project_name(&project_path);
+    let mut collector = if needs_findings {
+        Some(Collector::new(
+            &project_path,
+            &config,
+            &cli.correlation,
+            &cli.filters,
+            changed.as_deref().map(|c| (c, cli.changed_lines_only)),
+        )?)
+    } else {
+        None
+    };
+    // JSON Lines go out as each tool's output is parsed when nothing later
+    // changes the findings
+    let mut stream = match &collector {
+        Some(c) if c.is_final() && earlier.is_none() && formats.contains(&OutputFormat::Jsonl) => {
+            let report = output::Report {
+                runs: &[],
+                failed_tools: &[],
+                metadata: &metadata,
+                findings: &[],
+                fixed: &[],
+                output_dir: &output_dir,
+                project_name: &project_name,
+                project_path: &project_path,
+                tool_configs: &config.tools,
+                options: &config.report,
+                to_stdout,
+            };
+            Some(output::jsonl::Stream::create(&report).await?)
+        }
+        _ => None,
+    };
+
+    // Run tools in parallel, parsing each one's output as it finishes
+    let mut running = runner::spawn_tools(
+        &tools_to_run,
+        &config.tools,
+        &project_path,
+        &output_dir,
+        &options,
+    );
+    let mut results = Vec::new();
+    while let Some(result) = running.next().await {
+        if let (Some(collector), Ok(run)) = (&mut collector, &result) {
+            let findings = collector.add(std::slice::from_ref(run))?;
+            if let Some(stream) = &mut stream {
+                stream.write(findings).await?;
+            }
+        }
+        results.push(result);
+    }
+    runner::sort_results(&mut results);
+    let streamed = stream.is_some();
+    if let Some(stream) = stream {
+        stream.finish().await?;
+    }
 
     // Collect successful runs
     let mut successful_runs = Vec::new();
-    let mut failures = 0;
+    let mut failed_tools = Vec::new();
     for result in results {
         match result {
             Ok(run) => successful_runs.push(run),
-            Err(e) => {
-                eprintln!("[sast] Tool failed: {e}");
-                failures += 1;
+            Err(failure) => {
+                eprintln!("[sast] Tool failed: {}: {}", failure.name, failure.error);
+                failed_tools.push(failure);
             }
         }
     }
@@ -81,48 +530,217 @@ async fn main() -> Result<()> {
         anyhow::bail!("All tools failed to execute");
     }
 
-    // Parse findings if needed (skip for native mode)
-    let findings = if format != OutputFormat::Native {
-        let mut all_findings = Vec::new();
-        for run in &successful_runs {
-            match tools::parse_output(&run.tool_name, run) {
-                Ok(f) => all_findings.extend(f),
-                Err(e) => {
-                    tracing::warn!("Failed to parse {} output: {e}", run.tool_name);
-                }
-            }
-        }
-        all_findings
-    } else {
-        Vec::new()
-    };
+    metadata.finish(&successful_runs, &failed_tools);
 
-    // Write output
-    let project_name = cli
-        .path
-        .file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .unwrap_or_else(|| "project".to_string());
+    if cli.save_raw || config.defaults.save_raw.unwrap_or(false) {
+        replay::save(&successful_runs, &report_dir.join("raw"), &metadata).await?;
+    }
 
-    output::write_output(
-        format,
-        &successful_runs,
-        &findings,
-        &output_dir,
-        &project_name,
-        &config.tools,
-    )
-    .await?;
+    let mut findings = match collector {
+        Some(collector) => collector.finish(&mut metadata),
+        None => Vec::new(),
+    };
+    let mut fixed = Vec::new();
+    if let (Some(report), Some(earlier)) = (&cli.compare_to, earlier) {
+        (findings, fixed) =
+            compare_findings(earlier, findings, report, cli.new_only, &mut metadata);
+    }
+
+    // Write output; JSON Lines may already be out
+    let report = output::Report {
+        runs: &successful_runs,
+        failed_tools: &failed_tools,
+        metadata: &metadata,
+        findings: &findings,
+        fixed: &fixed,
+        output_dir: &output_dir,
+        project_name: &project_name,
+        project_path: &project_path,
+        tool_configs: &config.tools,
+        options: &config.report,
+        to_stdout,
+    };
+    let formats: Vec<OutputFormat> = formats
+        .into_iter()
+        .filter(|f| !(streamed && *f == OutputFormat::Jsonl))
+        .collect();
+    let written = output::write_output(&formats, &report).await;
+    if to_stdout {
+        let _ = tokio::fs::remove_dir_all(&output_dir).await;
+    }
+    written?;
 
     eprintln!();
     eprintln!("===== SAST Complete =====");
-    eprintln!("Reports:  {}", output_dir.display());
-    if failures > 0 {
-        eprintln!("Warning:  {} tool(s) failed", failures);
+    if to_stdout {
+        eprintln!("Reports:  stdout");
+    } else {
+        eprintln!("Reports:  {}", output_dir.display());
     }
-    if format != OutputFormat::Native {
+    if !skipped_tools.is_empty() {
+        let names: Vec<String> = skipped_tools
+            .iter()
+            .map(|s| format!("{} ({})", s.name, s.reason))
+            .collect();
+        eprintln!("Skipped:  {}", names.join(", "));
+    }
+    if !failed_tools.is_empty() {
+        eprintln!("Warning:  {} tool(s) failed", failed_tools.len());
+    }
+    if needs_findings {
         eprintln!("Findings: {}", findings.len());
     }
+    if let Some(baseline) = &metadata.baseline {
+        eprintln!(
+            "Baseline: {} new, {} fixed, {} unchanged",
+            baseline.new, baseline.fixed, baseline.unchanged
+        );
+    }
+    if let Some(min) = cli.fail_on_new {
+        baseline::gate(&findings, min)?;
+    }
+
+    Ok(())
+}
+
+async fn replay(
+    dir: &Path,
+    cli_formats: Option<Vec<String>>,
+    output: Option<PathBuf>,
+    stdout: bool,
+    config_path: Option<&Path>,
+    correlation: &cli::CorrelationArgs,
+    filters: &cli::FilterArgs,
+) -> Result<()> {
+    let config = config::load_config(config_path, None)?;
+    let formats = resolve_formats(cli_formats, &config)?;
+    let (output, to_stdout) = split_stdout(output, stdout);
+    if to_stdout {
+        output::check_stdout(&formats)?;
+    }
+
+    let (manifest, runs) = replay::load(dir).await?;
+    let output_dir = output.unwrap_or_else(|| {
+        let raw = replay::raw_dir(dir);
+        raw.parent().map(Path::to_path_buf).unwrap_or(raw)
+    });
+    if !to_stdout {
+        tokio::fs::create_dir_all(&output_dir).await?;
+    }
+
+    eprintln!("===== SAST Replay =====");
+    eprintln!("Project:  {}", manifest.project_path.display());
+    for run in &runs {
+        eprintln!(
+            "Tool:     {} ({}, exit code {}, {:.1}s)",
+            run.tool_name,
+            run.version.as_deref().unwrap_or("unknown version"),
+            run.exit_code,
+            run.duration.as_secs_f64()
+        );
+    }
+    if to_stdout {
+        eprintln!("Output:   stdout");
+    } else {
+        eprintln!("Output:   {}", output_dir.display());
+    }
+    eprintln!();
+
+    // Manifests written before metadata was recorded only have the runs
+    let mut metadata = manifest.metadata.clone().unwrap_or_else(|| {
+        metadata::ScanMetadata::from_runs(&manifest.sast_version, &manifest.project_path, &runs)
+    });
+
+    let findings = collect_findings(
+        &runs,
+        &manifest.project_path,
+        &config,
+        correlation,
+        filters,
+        None,
+        &mut metadata,
+    )?;
+    let report = output::Report {
+        runs: &runs,
+        failed_tools: &[],
+        metadata: &metadata,
+        findings: &findings,
+        fixed: &[],
+        output_dir: &output_dir,
+        project_name: &project_name(&manifest.project_path),
+        project_path: &manifest.project_path,
+        tool_configs: &config.tools,
+        options: &config.report,
+        to_stdout,
+    };
+    output::write_output(&formats, &report).await?;
+
+    eprintln!();
+    eprintln!("===== SAST Replay Complete =====");
+    eprintln!("Findings: {}", findings.len());
+    Ok(())
+}
+
+async fn diff(
+    old: &Path,
+    new: &Path,
+    cli_formats: Option<Vec<String>>,
+    output: Option<PathBuf>,
+    config_path: Option<&Path>,
+    new_only: bool,
+    fail_on_new: Option<model::Severity>,
+) -> Result<()> {
+    let config = config::load_config(config_path, None)?;
+    // The config's default formats are for scans; a diff is read in the terminal
+    let formats =
+        OutputFormat::parse_list(&cli_formats.unwrap_or_else(|| vec!["pretty".to_string()]))?;
+    ensure!(
+        !formats.contains(&OutputFormat::Native),
+        "The native format holds tool outputs; `sast diff` compares findings"
+    );
+    if let Some(format) = formats.iter().find(|f| !f.shows_comparison()) {
+        anyhow::bail!(
+            "The {} format has no way to show fixed findings; `sast diff` writes sarif, html, json, markdown, junit, csv, jsonl, pretty and template",
+            format.name()
+        );
+    }
+    let to_stdout = output.is_none();
+    if to_stdout {
+        output::check_stdout(&formats)?;
+    }
+    let output_dir = output.unwrap_or_default();
+    if !to_stdout {
+        tokio::fs::create_dir_all(&output_dir).await?;
+    }
 
+    let (_, earlier) = baseline::load(old).await?;
+    let (metadata, current) = baseline::load(new).await?;
+    let mut metadata = metadata.with_context(|| {
+        format!(
+            "'{}' has no scan metadata; scan again to compare with it",
+            new.display()
+        )
+    })?;
+    let (findings, fixed) = compare_findings(earlier, current, old, new_only, &mut metadata);
+
+    let project_path = metadata.project_path.clone();
+    let report = output::Report {
+        runs: &[],
+        failed_tools: &[],
+        metadata: &metadata,
+        findings: &findings,
+        fixed: &fixed,
+        output_dir: &output_dir,
+        project_name: &project_name(&project_path),
+        project_path: &project_path,
+        tool_configs: &config.tools,
+        options: &config.report,
+        to_stdout,
+    };
+    output::write_output(&formats, &report).await?;
+
+    if let Some(min) = fail_on_new {
+        baseline::gate(&findings, min)?;
+    }
     Ok(())
 }
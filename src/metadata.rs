@@ -0,0 +1,289 @@
+use crate::config::Config;
+use crate::filter::FilterCount;
+use crate::runner::{FailedTool, ToolRun};
+use chrono::{DateTime, Utc};
+use std::path::{Path, PathBuf};
+
+/// When, where and with what a scan was produced; embedded in the reports
+/// so they can be compared later.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ScanMetadata {
+    pub sast_version: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub duration_secs: f64,
+    pub host: Option<String>,
+    pub project_path: PathBuf,
+    /// Directory the tools ran in
+    #[serde(default)]
+    pub working_directory: Option<PathBuf>,
+    /// SHA-256 of the resolved configuration
+    pub config_hash: Option<String>,
+    pub git: Option<GitInfo>,
+    pub tools: Vec<ToolMetadata>,
+    /// Findings each configured filter left out of the report
+    #[serde(default)]
+    pub filtered: Vec<FilterCount>,
+    /// Set when the findings were compared with an earlier report
+    #[serde(default)]
+    pub baseline: Option<BaselineSummary>,
+}
+
+/// How the findings compare with an earlier report.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BaselineSummary {
+    /// The report compared with
+    pub report: PathBuf,
+    pub new: usize,
+    pub fixed: usize,
+    pub unchanged: usize,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct GitInfo {
+    pub commit: String,
+    /// `None` on a detached HEAD
+    pub branch: Option<String>,
+    /// Uncommitted changes to tracked or untracked files
+    pub dirty: bool,
+    /// URL of the `origin` remote, without credentials
+    pub remote: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ToolMetadata {
+    pub name: String,
+    pub version: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub duration_secs: Option<f64>,
+    pub exit_code: Option<i32>,
+    /// Set when the tool could not be executed
+    pub error: Option<String>,
+}
+
+impl ScanMetadata {
+    /// Metadata for a scan starting now, before any tool runs: the revision
+    /// is read while the tree still holds only the user's changes, and
+    /// `output_dir` is left out of the dirty check. See [`Self::finish`].
+    pub async fn start(
+        started_at: DateTime<Utc>,
+        project_path: &Path,
+        output_dir: &Path,
+        config: &Config,
+    ) -> Self {
+        Self {
+            sast_version: env!("CARGO_PKG_VERSION").to_string(),
+            started_at,
+            finished_at: started_at,
+            duration_secs: 0.0,
+            host: hostname(),
+            project_path: std::fs::canonicalize(project_path)
+                .unwrap_or_else(|_| project_path.to_path_buf()),
+            working_directory: std::env::current_dir().ok(),
+            config_hash: Some(config.hash()),
+            git: git_info(project_path, output_dir).await,
+            tools: Vec::new(),
+            filtered: Vec::new(),
+            baseline: None,
+        }
+    }
+
+    /// Record the end of the scan and what its tools did.
+    pub fn finish(&mut self, runs: &[ToolRun], failed_tools: &[FailedTool]) {
+        self.finished_at = Utc::now();
+        self.duration_secs = (self.finished_at - self.started_at)
+            .to_std()
+            .unwrap_or_default()
+            .as_secs_f64();
+        self.tools = tool_metadata(runs);
+        self.tools.extend(failed_tools.iter().map(|f| ToolMetadata {
+            name: f.name.clone(),
+            version: None,
+            started_at: None,
+            duration_secs: None,
+            exit_code: None,
+            error: Some(f.error.clone()),
+        }));
+    }
+
+    /// Best-effort metadata for recorded runs that came without any: times
+    /// and versions from the runs, nothing about the host or revision.
+    pub fn from_runs(sast_version: &str, project_path: &Path, runs: &[ToolRun]) -> Self {
+        let started_at = runs.iter().map(|r| r.started_at).min().unwrap_or_default();
+        let finished_at = runs
+            .iter()
+            .map(|r| r.started_at + chrono::Duration::from_std(r.duration).unwrap_or_default())
+            .max()
+            .unwrap_or(started_at);
+
+        Self {
+            sast_version: sast_version.to_string(),
+            started_at,
+            finished_at,
+            duration_secs: (finished_at - started_at)
+                .to_std()
+                .unwrap_or_default()
+                .as_secs_f64(),
+            host: None,
+            project_path: project_path.to_path_buf(),
+            working_directory: None,
+            config_hash: None,
+            git: None,
+            tools: tool_metadata(runs),
+            filtered: Vec::new(),
+            baseline: None,
+        }
+    }
+
+    /// Short `key: value` lines for formats with free-form metadata.
+    pub fn summary(&self) -> Vec<(&'static str, String)> {
+        let time = |t: DateTime<Utc>| t.to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        let mut items = vec![
+            ("sast", self.sast_version.clone()),
+            ("started", time(self.started_at)),
+            ("finished", time(self.finished_at)),
+            ("duration", format!("{:.1}s", self.duration_secs)),
+        ];
+        if let Some(git) = &self.git {
+            let mut revision = git.commit.clone();
+            if let Some(branch) = &git.branch {
+                revision.push_str(&format!(" ({branch})"));
+            }
+            if git.dirty {
+                revision.push_str(" +uncommitted changes");
+            }
+            items.push(("commit", revision));
+        }
+        if let Some(host) = &self.host {
+            items.push(("host", host.clone()));
+        }
+        let versions: Vec<String> = self
+            .tools
+            .iter()
+            .map(|t| match (&t.version, &t.error) {
+                (_, Some(_)) => format!("{} (failed)", t.name),
+                (Some(v), None) => format!("{} ({v})", t.name),
+                (None, None) => t.name.clone(),
+            })
+            .collect();
+        items.push(("tools", versions.join(", ")));
+        if !self.filtered.is_empty() {
+            let counts: Vec<String> = self
+                .filtered
+                .iter()
+                .map(|c| format!("{} {}", c.filter, c.removed))
+                .collect();
+            items.push(("filtered", counts.join(", ")));
+        }
+        if let Some(baseline) = &self.baseline {
+            items.push((
+                "baseline",
+                format!(
+                    "{} new, {} fixed, {} unchanged since {}",
+                    baseline.new,
+                    baseline.fixed,
+                    baseline.unchanged,
+                    baseline.report.display()
+                ),
+            ));
+        }
+        if let Some(hash) = &self.config_hash {
+            items.push(("config", hash[..12.min(hash.len())].to_string()));
+        }
+        items
+    }
+}
+
+fn tool_metadata(runs: &[ToolRun]) -> Vec<ToolMetadata> {
+    runs.iter()
+        .map(|r| ToolMetadata {
+            name: r.tool_name.clone(),
+            version: r.version.clone(),
+            started_at: Some(r.started_at),
+            duration_secs: Some(r.duration.as_secs_f64()),
+            exit_code: Some(r.exit_code),
+            error: None,
+        })
+        .collect()
+}
+
+fn hostname() -> Option<String> {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+}
+
+/// Revision of the git work tree holding the project, if any.
+async fn git_info(project_path: &Path, output_dir: &Path) -> Option<GitInfo> {
+    let commit = git(project_path, &["rev-parse", "HEAD"]).await?;
+    let branch = git(project_path, &["rev-parse", "--abbrev-ref", "HEAD"])
+        .await
+        .filter(|b| b != "HEAD");
+    // Reports of earlier scans in the project don't make it dirty
+    let exclude = project_subdir(project_path, output_dir)
+        .map(|dir| format!(":(exclude,literal){}", dir.display()));
+    let mut status = vec!["status", "--porcelain"];
+    if let Some(exclude) = &exclude {
+        status.extend(["--", exclude]);
+    }
+    let dirty = git(project_path, &status)
+        .await
+        .is_some_and(|s| !s.is_empty());
+    let remote = git(project_path, &["remote", "get-url", "origin"])
+        .await
+        .map(|url| redact_url(&url));
+    Some(GitInfo {
+        commit,
+        branch,
+        dirty,
+        remote,
+    })
+}
+
+/// `dir` relative to the project when it lies inside it. A directory that
+/// doesn't exist yet can only be matched as given.
+fn project_subdir(project_path: &Path, dir: &Path) -> Option<PathBuf> {
+    let relative = match dir.strip_prefix(project_path) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => {
+            let root = std::fs::canonicalize(project_path).ok()?;
+            let dir = std::fs::canonicalize(dir).ok()?;
+            dir.strip_prefix(root).ok()?.to_path_buf()
+        }
+    };
+    (!relative.as_os_str().is_empty()).then_some(relative)
+}
+
+/// `url` without credentials: CI checkouts often carry a token as userinfo
+/// (`https://gitlab-ci-token:<token>@host/...`) or in the query, and reports
+/// get shared. The query and fragment are dropped; scp-style addresses
+/// (`git@host:path`) name only an ssh user and stay as they are.
+fn redact_url(url: &str) -> String {
+    let url = url.split(['?', '#']).next().unwrap_or_default();
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+    let authority_end = rest.find('/').unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(authority_end);
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    format!("{scheme}://{host}{path}")
+}
+
+async fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = tokio::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
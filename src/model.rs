@@ -1,5 +1,36 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
+/// Version of the flat finding layout written by the `csv` and `jsonl`
+/// formats. Fields are only ever appended; renaming or removing one bumps
+/// the version.
+pub const FLAT_SCHEMA_VERSION: u32 = 1;
+
+/// Fields of the flat layout, in column order. `file` is relative to the
+/// project when possible; `line` and `column` are 1-based.
+/// `cwe` is the numeric id of the primary CWE; `tools` lists every tool
+/// that reported the finding, comma-separated.
+pub const FLAT_FIELDS: &[&str] = &[
+    "tool",
+    "rule_id",
+    "severity",
+    "file",
+    "line",
+    "column",
+    "message",
+    "end_line",
+    "end_column",
+    "cwe",
+    "confidence",
+    "help_uri",
+    "fingerprint",
+    "tools",
+    "tool_severity",
+    "security_severity",
+    "kind",
+    "baseline_state",
+];
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Finding {
     pub tool: String,
@@ -7,16 +38,241 @@ pub struct Finding {
     pub severity: Severity,
     pub message: String,
     pub location: Location,
+    /// Source text at the location, as printed by the tool
+    #[serde(default)]
+    pub snippet: Option<String>,
+    /// CWE ids of the weakness, most specific first
+    #[serde(default)]
+    pub cwe: Vec<u32>,
+    /// OWASP Top 10 categories, e.g. `A03:2021 - Injection`
+    #[serde(default)]
+    pub owasp: Vec<String>,
+    #[serde(default)]
+    pub confidence: Option<Confidence>,
+    /// Documentation for the rule
+    #[serde(default)]
+    pub help_uri: Option<String>,
+    /// Other places the tool pointed at, e.g. compiler notes
+    #[serde(default)]
+    pub related_locations: Vec<RelatedLocation>,
+    /// Steps from source to sink for dataflow findings, in order
+    #[serde(default)]
+    pub code_flow: Vec<RelatedLocation>,
+    #[serde(default)]
+    pub fix: Option<Fix>,
+    /// Tool-specific data without a field of its own
+    #[serde(default)]
+    pub properties: BTreeMap<String, serde_json::Value>,
+    /// Identity across scans; see [`crate::fingerprint`]
+    #[serde(default)]
+    pub fingerprint: String,
+    /// Findings of other tools about the same weakness, merged into this one
+    #[serde(default)]
+    pub correlated: Vec<Correlated>,
+    /// Severity as the tool printed it, e.g. cppcheck's `performance`,
+    /// before `severity_map` and `severity_overrides` were applied
+    #[serde(default)]
+    pub tool_severity: Option<String>,
+    /// How bad a vulnerability is; only set for `Kind::Vulnerability`
+    #[serde(default)]
+    pub security_severity: Option<SecuritySeverity>,
+    #[serde(default)]
+    pub kind: Option<Kind>,
+    /// Whether the finding is new since the report it was compared with
+    #[serde(default)]
+    pub baseline_state: Option<BaselineState>,
+}
+
+impl Finding {
+    /// A finding with only the fields every tool reports; parsers fill in
+    /// the rest as they know it.
+    pub fn new(
+        tool: &str,
+        rule_id: Option<String>,
+        severity: Severity,
+        message: String,
+        location: Location,
+    ) -> Self {
+        Self {
+            tool: tool.to_string(),
+            rule_id,
+            severity,
+            message,
+            location,
+            snippet: None,
+            cwe: Vec::new(),
+            owasp: Vec::new(),
+            confidence: None,
+            help_uri: None,
+            related_locations: Vec::new(),
+            code_flow: Vec::new(),
+            fix: None,
+            properties: BTreeMap::new(),
+            fingerprint: String::new(),
+            correlated: Vec::new(),
+            tool_severity: None,
+            security_severity: None,
+            kind: None,
+            baseline_state: None,
+        }
+    }
+
+    /// Every tool that reported this finding, sorted.
+    pub fn tools(&self) -> Vec<&str> {
+        let mut tools: Vec<&str> = std::iter::once(self.tool.as_str())
+            .chain(self.correlated.iter().map(|c| c.tool.as_str()))
+            .collect();
+        tools.sort();
+        tools.dedup();
+        tools
+    }
+}
+
+/// A finding folded into another by correlation; see [`crate::correlate`].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Correlated {
+    pub tool: String,
+    pub rule_id: Option<String>,
+    pub severity: Severity,
+    pub message: String,
+    pub location: Location,
+    pub fingerprint: String,
 }
 
+/// Lines and columns are 1-based; the end is inclusive.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Location {
     pub file: PathBuf,
     pub line: Option<u32>,
     pub column: Option<u32>,
+    #[serde(default)]
+    pub end_line: Option<u32>,
+    #[serde(default)]
+    pub end_column: Option<u32>,
+}
+
+impl Location {
+    pub fn new(file: impl Into<PathBuf>, line: Option<u32>, column: Option<u32>) -> Self {
+        Self {
+            file: file.into(),
+            line,
+            column,
+            end_line: None,
+            end_column: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RelatedLocation {
+    pub location: Location,
+    pub message: Option<String>,
+}
+
+/// Replacement for the text at `location`, or else at the finding's
+/// location; an empty range (no end) is an insertion.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Fix {
+    pub description: Option<String>,
+    pub replacement: String,
+    /// Set when the tool places the change itself, e.g. clang fix-its
+    #[serde(default)]
+    pub location: Option<Location>,
+}
+
+/// How sure the tool is that the finding is real.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+    High,
+    Medium,
+    Low,
+}
+
+impl Confidence {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Confidence::High => "high",
+            Confidence::Medium => "medium",
+            Confidence::Low => "low",
+        }
+    }
+}
+
+/// Impact of a vulnerability, on the scale code-scanning UIs rank by.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum SecuritySeverity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl SecuritySeverity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SecuritySeverity::Critical => "critical",
+            SecuritySeverity::High => "high",
+            SecuritySeverity::Medium => "medium",
+            SecuritySeverity::Low => "low",
+        }
+    }
+
+    /// CVSS-style score in the middle of the level's band, as SARIF
+    /// `security-severity` expects
+    pub fn score(&self) -> &'static str {
+        match self {
+            SecuritySeverity::Critical => "9.5",
+            SecuritySeverity::High => "8.0",
+            SecuritySeverity::Medium => "5.5",
+            SecuritySeverity::Low => "2.0",
+        }
+    }
+}
+
+/// What kind of problem a finding is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    Vulnerability,
+    Bug,
+    CodeSmell,
 }
 
-#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+impl Kind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::Vulnerability => "vulnerability",
+            Kind::Bug => "bug",
+            Kind::CodeSmell => "code-smell",
+        }
+    }
+}
+
+/// A finding compared with an earlier report, matched by fingerprint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BaselineState {
+    New,
+    Unchanged,
+    /// Only in the earlier report
+    Fixed,
+}
+
+impl BaselineState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BaselineState::New => "new",
+            BaselineState::Unchanged => "unchanged",
+            BaselineState::Fixed => "fixed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Severity {
     Error,
@@ -26,14 +282,53 @@ pub enum Severity {
     Note,
 }
 
-impl std::fmt::Display for Severity {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl Severity {
+    /// All severities, most severe first
+    pub const ALL: [Severity; 5] = [
+        Severity::Error,
+        Severity::Warning,
+        Severity::Style,
+        Severity::Info,
+        Severity::Note,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+            Severity::Style => "style",
+            Severity::Note => "note",
+        }
+    }
+
+    /// Higher is more severe; used for sorting and thresholds
+    pub fn rank(&self) -> u8 {
         match self {
-            Severity::Error => write!(f, "error"),
-            Severity::Warning => write!(f, "warning"),
-            Severity::Info => write!(f, "info"),
-            Severity::Style => write!(f, "style"),
-            Severity::Note => write!(f, "note"),
+            Severity::Error => 4,
+            Severity::Warning => 3,
+            Severity::Style => 2,
+            Severity::Info => 1,
+            Severity::Note => 0,
         }
     }
 }
+
+impl std::str::FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|severity| severity.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                format!("unknown severity '{s}' (use error, warning, style, info or note)")
+            })
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
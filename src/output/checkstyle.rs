@@ -0,0 +1,96 @@
+use super::{display_path, emit, xml_escape, OutputFormat, Report};
+use crate::model::{Finding, Severity};
+use anyhow::{ensure, Result};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+const SEVERITIES: &[&str] = &["error", "warning", "info", "ignore"];
+
+pub async fn write(report: &Report<'_>) -> Result<()> {
+    let severity_map = &report.options.checkstyle.severity_map;
+    for value in severity_map.values() {
+        ensure!(
+            SEVERITIES.contains(&value.as_str()),
+            "Invalid checkstyle severity '{}'. Use: {}",
+            value,
+            SEVERITIES.join(", ")
+        );
+    }
+
+    let mut by_file: BTreeMap<String, Vec<&Finding>> = BTreeMap::new();
+    for f in report.findings {
+        by_file
+            .entry(display_path(&f.location.file, report.project_path))
+            .or_default()
+            .push(f);
+    }
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    // Checkstyle has no metadata element; keep it readable as a comment
+    for (key, value) in report.metadata.summary() {
+        let _ = writeln!(out, "<!-- {} -->", xml_comment(&format!("{key}: {value}")));
+    }
+    let _ = writeln!(out, "<checkstyle version=\"4.3\">");
+    for (file, findings) in &mut by_file {
+        findings.sort_by_key(|f| (f.location.line, f.location.column));
+        let _ = writeln!(out, "  <file name=\"{}\">", xml_escape(file));
+        for f in findings.iter() {
+            let severity = severity_map
+                .get(&f.severity)
+                .map(String::as_str)
+                .unwrap_or_else(|| default_severity(f.severity));
+            let _ = write!(out, "    <error");
+            if let Some(line) = f.location.line {
+                let _ = write!(out, " line=\"{line}\"");
+            }
+            if let Some(column) = f.location.column {
+                let _ = write!(out, " column=\"{column}\"");
+            }
+            let _ = writeln!(
+                out,
+                " severity=\"{}\" message=\"{}\" source=\"{}\"/>",
+                severity,
+                xml_escape(&f.message),
+                xml_escape(&source(f))
+            );
+        }
+        let _ = writeln!(out, "  </file>");
+    }
+    let _ = writeln!(out, "</checkstyle>");
+
+    emit(report, OutputFormat::Checkstyle, "checkstyle.xml", out).await
+}
+
+/// Text an XML comment can hold: no `--`, no trailing `-` and no
+/// characters XML 1.0 can't represent.
+fn xml_comment(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '-' if out.ends_with('-') => out.push_str(" -"),
+            '\n' | '\r' | '\t' => out.push(c),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    if out.ends_with('-') {
+        out.push(' ');
+    }
+    out
+}
+
+fn default_severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Style | Severity::Info | Severity::Note => "info",
+    }
+}
+
+/// Checkstyle names the check that fired in `source`, e.g. `cppcheck.doubleFree`.
+fn source(f: &Finding) -> String {
+    match &f.rule_id {
+        Some(rule) => format!("{}.{}", f.tool, rule),
+        None => f.tool.clone(),
+    }
+}
@@ -0,0 +1,52 @@
+use super::{flat_field, OutputFormat, Report, ReportSink};
+use crate::model::FLAT_FIELDS;
+use anyhow::{ensure, Result};
+use serde_json::Value;
+
+/// `report.csv`: a header row, then one finding per row in the flat layout,
+/// fixed findings of a comparison last.
+pub async fn write(report: &Report<'_>) -> Result<()> {
+    let columns: Vec<&str> = match &report.options.csv.columns {
+        Some(columns) => columns.iter().map(String::as_str).collect(),
+        None => FLAT_FIELDS.to_vec(),
+    };
+    for column in &columns {
+        ensure!(
+            FLAT_FIELDS.contains(column),
+            "Unknown CSV column '{}'. Use: {}",
+            column,
+            FLAT_FIELDS.join(", ")
+        );
+    }
+    ensure!(!columns.is_empty(), "No CSV columns configured");
+
+    let mut out = ReportSink::create(report, OutputFormat::Csv, "report.csv").await?;
+    out.write(row(columns.iter().map(|c| c.to_string())).as_bytes())
+        .await?;
+    for f in report.findings.iter().chain(report.fixed) {
+        let cells = columns.iter().map(|c| {
+            match flat_field(f, c, report.project_path).unwrap_or_default() {
+                Value::Null => String::new(),
+                Value::String(s) => s,
+                other => other.to_string(),
+            }
+        });
+        out.write(row(cells).as_bytes()).await?;
+    }
+    out.finish().await
+}
+
+/// One RFC 4180 record, CRLF-terminated.
+fn row(cells: impl Iterator<Item = String>) -> String {
+    let mut line = cells.map(|c| quote(&c)).collect::<Vec<_>>().join(",");
+    line.push_str("\r\n");
+    line
+}
+
+fn quote(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
@@ -0,0 +1,232 @@
+use super::{display_path, emit, OutputFormat, Report};
+use crate::model::{Finding, Kind, SecuritySeverity, Severity};
+use anyhow::Result;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// Version of the GitLab security report schema the SAST report follows.
+const SAST_SCHEMA_VERSION: &str = "15.0.7";
+
+/// `gl-sast-report.json`, the artifact behind `artifacts:reports:sast`.
+pub async fn write_sast(report: &Report<'_>) -> Result<()> {
+    let fingerprints = unique_fingerprints(report);
+    let vulnerabilities: Vec<Value> = report
+        .findings
+        .iter()
+        .zip(&fingerprints)
+        .map(|(f, fp)| vulnerability(f, fp, report))
+        .collect();
+
+    let analyzer = json!({
+        "id": "sast",
+        "name": "sast",
+        "version": env!("CARGO_PKG_VERSION"),
+        "vendor": { "name": "sast" }
+    });
+    let time = |t: chrono::DateTime<chrono::Utc>| t.format("%Y-%m-%dT%H:%M:%S").to_string();
+    let sast = json!({
+        "version": SAST_SCHEMA_VERSION,
+        "scan": {
+            "analyzer": analyzer,
+            "scanner": analyzer,
+            "type": "sast",
+            "start_time": time(report.metadata.started_at),
+            "end_time": time(report.metadata.finished_at),
+            "status": "success"
+        },
+        "vulnerabilities": vulnerabilities
+    });
+
+    let json = serde_json::to_string_pretty(&sast)?;
+    emit(
+        report,
+        OutputFormat::GitlabSast,
+        "gl-sast-report.json",
+        json,
+    )
+    .await
+}
+
+/// `gl-code-quality-report.json`, the artifact behind
+/// `artifacts:reports:codequality`.
+pub async fn write_code_quality(report: &Report<'_>) -> Result<()> {
+    let fingerprints = unique_fingerprints(report);
+    let issues: Vec<Value> = report
+        .findings
+        .iter()
+        .zip(&fingerprints)
+        .map(|(f, fp)| {
+            json!({
+                "type": "issue",
+                "check_name": check_name(f),
+                "description": f.message,
+                "engine_name": f.tool,
+                "categories": [category(f)],
+                "fingerprint": fp,
+                "severity": code_quality_severity(f.severity),
+                "location": {
+                    "path": display_path(&f.location.file, report.project_path),
+                    "lines": lines(f)
+                }
+            })
+        })
+        .collect();
+
+    let json = serde_json::to_string_pretty(&issues)?;
+    emit(
+        report,
+        OutputFormat::GitlabCodeQuality,
+        "gl-code-quality-report.json",
+        json,
+    )
+    .await
+}
+
+fn vulnerability(f: &Finding, fp: &str, report: &Report<'_>) -> Value {
+    let mut identifiers = vec![json!({
+        "type": format!("{}_rule_id", f.tool.replace('-', "_")),
+        "name": check_name(f),
+        "value": check_name(f)
+    })];
+    for c in &f.correlated {
+        if let Some(rule) = &c.rule_id {
+            identifiers.push(json!({
+                "type": format!("{}_rule_id", c.tool.replace('-', "_")),
+                "name": rule,
+                "value": rule
+            }));
+        }
+    }
+    for cwe in &f.cwe {
+        identifiers.push(json!({
+            "type": "cwe",
+            "name": format!("CWE-{cwe}"),
+            "value": cwe.to_string(),
+            "url": format!("https://cwe.mitre.org/data/definitions/{cwe}.html")
+        }));
+    }
+    for owasp in &f.owasp {
+        identifiers.push(json!({
+            "type": "owasp",
+            "name": owasp,
+            "value": owasp.split_once(" - ").map_or(owasp.as_str(), |(id, _)| id)
+        }));
+    }
+    if let Some(uri) = &f.help_uri {
+        identifiers[0]["url"] = uri.as_str().into();
+    }
+
+    let mut location = json!({
+        "file": display_path(&f.location.file, report.project_path)
+    });
+    if let Some(line) = f.location.line.filter(|l| *l > 0) {
+        location["start_line"] = line.into();
+        location["end_line"] = f.location.end_line.unwrap_or(line).max(line).into();
+    }
+
+    let mut vulnerability = json!({
+        "id": uuid(fp),
+        "name": check_name(f),
+        "description": f.message,
+        "severity": sast_severity(f),
+        "scanner": { "id": f.tool, "name": f.tool },
+        "location": location,
+        "identifiers": identifiers
+    });
+    if let Some(fix) = &f.fix {
+        vulnerability["solution"] = match &fix.description {
+            Some(description) => format!("{description}: {}", fix.replacement),
+            None => format!("Replace with: {}", fix.replacement),
+        }
+        .into();
+    }
+    if let Some(uri) = &f.help_uri {
+        vulnerability["links"] = json!([{ "url": uri }]);
+    }
+    vulnerability
+}
+
+/// Fingerprints for every finding, in order. Identical findings (same tool,
+/// rule, file and message) are told apart by occurrence so each one keeps its
+/// own id while staying stable between pipelines.
+fn unique_fingerprints(report: &Report<'_>) -> Vec<String> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    report
+        .findings
+        .iter()
+        .map(|f| {
+            let fp = f.fingerprint.clone();
+            let count = seen.entry(fp.clone()).or_default();
+            *count += 1;
+            if *count == 1 {
+                fp
+            } else {
+                format!("{:x}", Sha256::digest(format!("{fp}:{count}")))
+            }
+        })
+        .collect()
+}
+
+/// A UUID-shaped id from a hex fingerprint, as the schema expects.
+fn uuid(fp: &str) -> String {
+    let hex: String = fp.chars().filter(char::is_ascii_hexdigit).collect();
+    let hex = format!("{hex:0<32}");
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+fn lines(f: &Finding) -> Value {
+    let begin = f.location.line.unwrap_or(1).max(1);
+    match f.location.end_line.filter(|end| *end > begin) {
+        Some(end) => json!({ "begin": begin, "end": end }),
+        None => json!({ "begin": begin }),
+    }
+}
+
+fn check_name(f: &Finding) -> String {
+    f.rule_id.clone().unwrap_or_else(|| f.tool.clone())
+}
+
+/// The security severity of vulnerabilities, else one derived from ours.
+fn sast_severity(f: &Finding) -> &'static str {
+    if let Some(security_severity) = f.security_severity {
+        return match security_severity {
+            SecuritySeverity::Critical => "Critical",
+            SecuritySeverity::High => "High",
+            SecuritySeverity::Medium => "Medium",
+            SecuritySeverity::Low => "Low",
+        };
+    }
+    match f.severity {
+        Severity::Error => "High",
+        Severity::Warning => "Medium",
+        Severity::Style => "Low",
+        Severity::Info | Severity::Note => "Info",
+    }
+}
+
+fn code_quality_severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "critical",
+        Severity::Warning => "major",
+        Severity::Style => "minor",
+        Severity::Info | Severity::Note => "info",
+    }
+}
+
+fn category(f: &Finding) -> &'static str {
+    match f.kind {
+        Some(Kind::Vulnerability) => "Security",
+        Some(Kind::CodeSmell) => "Style",
+        Some(Kind::Bug) => "Bug Risk",
+        None if f.severity == Severity::Style => "Style",
+        None => "Bug Risk",
+    }
+}
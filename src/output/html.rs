@@ -1,36 +1,145 @@
-use crate::model::Finding;
+use super::{display_path, emit, html_escape, OutputFormat, Report, SourceCache};
+use crate::model::{BaselineState, Finding, RelatedLocation, Severity};
 use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
 use std::path::Path;
 
-pub async fn write(findings: &[Finding], output_dir: &Path, project_name: &str) -> Result<()> {
+/// Lines of source shown above and below a finding
+const CONTEXT_LINES: usize = 3;
+
+pub async fn write(report: &Report<'_>) -> Result<()> {
+    let findings = report.findings;
+    let mut sources = SourceCache::new(report.project_path);
+    let bug_pages = scan_build_pages(report.output_dir);
+
+    // Display paths relative to the project when possible
+    let display_paths: Vec<String> = findings
+        .iter()
+        .map(|f| display_path(&f.location.file, report.project_path))
+        .collect();
+
     let mut rows = String::new();
-    for f in findings {
+    for (f, file) in findings.iter().zip(&display_paths) {
         let severity_class = format!("{}", f.severity);
         let rule = f.rule_id.as_deref().unwrap_or("-");
-        let file = f.location.file.to_string_lossy();
         let line = f
             .location
             .line
             .map(|l| l.to_string())
             .unwrap_or_else(|| "-".to_string());
 
-        rows.push_str(&format!(
-            r#"<tr class="severity-{sev}">
+        let mut details = tags(f);
+        // The tool's own copy of the line when the source isn't readable here
+        let source = f
+            .location
+            .line
+            .and_then(|l| snippet(&mut sources, &f.location.file, l as usize))
+            .or_else(|| f.snippet.as_deref().map(html_escape));
+        if let Some(snippet) = source {
+            let _ = write!(
+                details,
+                "<details class=\"snippet\"><summary>Source</summary><pre>{snippet}</pre></details>"
+            );
+        }
+        for (title, steps) in [
+            ("Related locations", &f.related_locations),
+            ("Data flow", &f.code_flow),
+        ] {
+            if !steps.is_empty() {
+                let _ = write!(
+                    details,
+                    "<details class=\"snippet\"><summary>{title}</summary><ol>{}</ol></details>",
+                    steps_list(steps, report.project_path)
+                );
+            }
+        }
+        if let Some(fix) = &f.fix {
+            let _ = write!(
+                details,
+                "<div class=\"fix\">Fix: {}<code>{}</code></div>",
+                fix.description
+                    .as_deref()
+                    .map(|d| format!("{} ", html_escape(d)))
+                    .unwrap_or_default(),
+                html_escape(&fix.replacement)
+            );
+        }
+        if f.tool == "scan-build" {
+            for page in bug_pages.iter().filter(|p| p.matches(f)) {
+                let _ = write!(
+                    details,
+                    " <a class=\"bug-link\" href=\"{}\">scan-build report</a>",
+                    html_escape(&page.href)
+                );
+            }
+        }
+
+        let _ = write!(
+            rows,
+            r##"<tr class="finding severity-{sev}" data-tool="{tool}" data-severity="{sev}" data-rank="{rank}" data-file="{file}" data-line="{line_num}" data-rule="{rule}">
   <td>{tool}</td>
   <td>{sev}</td>
-  <td>{file}</td>
+  <td><a href="#" class="file-link">{file}</a></td>
   <td>{line}</td>
   <td>{rule}</td>
-  <td>{msg}</td>
+  <td>{msg}{details}</td>
 </tr>
-"#,
+"##,
             sev = severity_class,
+            rank = f.severity.rank(),
             tool = html_escape(&f.tool),
-            file = html_escape(&file),
+            file = html_escape(file),
+            line_num = f.location.line.unwrap_or(0),
             line = line,
             rule = html_escape(rule),
             msg = html_escape(&f.message),
-        ));
+            details = details,
+        );
+    }
+
+    let options = |values: BTreeSet<&str>| -> String {
+        values
+            .into_iter()
+            .map(|v| format!("<option value=\"{0}\">{0}</option>", html_escape(v)))
+            .collect()
+    };
+    let tool_options = options(findings.iter().map(|f| f.tool.as_str()).collect());
+    let severity_options = options(findings.iter().map(|f| f.severity.name()).collect());
+    let rule_options = options(
+        findings
+            .iter()
+            .filter_map(|f| f.rule_id.as_deref())
+            .collect(),
+    );
+
+    let mut file_counts: BTreeMap<&str, (usize, usize, usize)> = BTreeMap::new();
+    for (f, file) in findings.iter().zip(&display_paths) {
+        let entry = file_counts.entry(file.as_str()).or_default();
+        match f.severity {
+            Severity::Error => entry.0 += 1,
+            Severity::Warning => entry.1 += 1,
+            _ => entry.2 += 1,
+        }
+    }
+    let mut sidebar = String::new();
+    for (file, (errors, warnings, other)) in &file_counts {
+        let _ = writeln!(
+            sidebar,
+            r##"<li><a href="#" class="file-link" data-file="{file}">{file}</a> <span class="badges"><span class="badge b-error" title="errors">{errors}</span><span class="badge b-warning" title="warnings">{warnings}</span><span class="badge b-other" title="other">{other}</span></span></li>"##,
+            file = html_escape(file),
+        );
+    }
+    if !bug_pages.is_empty() {
+        sidebar.push_str("</ul><h2>scan-build pages</h2><ul>");
+        for page in &bug_pages {
+            let _ = write!(
+                sidebar,
+                "<li><a href=\"{}\">{}</a></li>",
+                html_escape(&page.href),
+                html_escape(&page.label())
+            );
+        }
     }
 
     let html = format!(
@@ -41,13 +150,25 @@ pub async fn write(findings: &[Finding], output_dir: &Path, project_name: &str)
 <meta name="viewport" content="width=device-width, initial-scale=1.0">
 <title>SAST Report - {project}</title>
 <style>
-  body {{ font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', sans-serif; margin: 2rem; background: #f5f5f5; }}
-  h1 {{ color: #333; }}
+  body {{ font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', sans-serif; margin: 0; background: #f5f5f5; }}
+  h1 {{ color: #333; margin-top: 0; }}
+  h2 {{ font-size: 1rem; color: #2c3e50; margin: 1rem 0 0.5rem; }}
+  .layout {{ display: flex; min-height: 100vh; }}
+  aside {{ width: 300px; flex-shrink: 0; background: #fff; border-right: 1px solid #e0e0e0; padding: 1rem; overflow-y: auto; max-height: 100vh; position: sticky; top: 0; box-sizing: border-box; }}
+  aside ul {{ list-style: none; padding: 0; margin: 0; font-size: 0.85rem; }}
+  aside li {{ padding: 4px 0; display: flex; justify-content: space-between; gap: 0.5rem; word-break: break-all; }}
+  main {{ flex: 1; padding: 2rem; min-width: 0; }}
   .summary {{ margin: 1rem 0; padding: 1rem; background: #fff; border-radius: 8px; box-shadow: 0 1px 3px rgba(0,0,0,0.1); }}
+  .filters {{ display: flex; flex-wrap: wrap; gap: 0.75rem; align-items: center; margin: 1rem 0; padding: 1rem; background: #fff; border-radius: 8px; box-shadow: 0 1px 3px rgba(0,0,0,0.1); }}
+  .filters label {{ font-size: 0.85rem; color: #555; }}
+  .filters select, .filters input {{ margin-left: 0.25rem; padding: 4px 6px; }}
   table {{ border-collapse: collapse; width: 100%; background: #fff; border-radius: 8px; overflow: hidden; box-shadow: 0 1px 3px rgba(0,0,0,0.1); }}
-  th {{ background: #2c3e50; color: #fff; padding: 12px; text-align: left; }}
-  td {{ padding: 10px 12px; border-bottom: 1px solid #eee; }}
+  th {{ background: #2c3e50; color: #fff; padding: 12px; text-align: left; cursor: pointer; user-select: none; white-space: nowrap; }}
+  th.sorted-asc::after {{ content: " \25B2"; }}
+  th.sorted-desc::after {{ content: " \25BC"; }}
+  td {{ padding: 10px 12px; border-bottom: 1px solid #eee; vertical-align: top; }}
   tr:hover {{ background: #f8f9fa; }}
+  tr.group td {{ background: #ecf0f1; font-weight: bold; color: #2c3e50; }}
   .severity-error td:nth-child(2) {{ color: #e74c3c; font-weight: bold; }}
   .severity-warning td:nth-child(2) {{ color: #f39c12; font-weight: bold; }}
   .severity-info td:nth-child(2) {{ color: #3498db; }}
@@ -57,47 +178,361 @@ pub async fn write(findings: &[Finding], output_dir: &Path, project_name: &str)
   .count-error {{ background: #fde8e8; color: #e74c3c; }}
   .count-warning {{ background: #fef3e2; color: #f39c12; }}
   .count-total {{ background: #e8f4fd; color: #2980b9; }}
+  .badge {{ display: inline-block; min-width: 1.5em; text-align: center; border-radius: 8px; margin-left: 2px; font-size: 0.75rem; }}
+  .b-error {{ background: #fde8e8; color: #e74c3c; }}
+  .b-warning {{ background: #fef3e2; color: #f39c12; }}
+  .b-other {{ background: #eef; color: #7f8c8d; }}
+  details.snippet {{ margin-top: 6px; }}
+  details.snippet summary {{ cursor: pointer; color: #2980b9; font-size: 0.85rem; }}
+  details.snippet pre {{ background: #272822; color: #f8f8f2; padding: 8px; border-radius: 4px; overflow-x: auto; font-size: 0.8rem; margin: 4px 0 0; }}
+  .ln {{ color: #75715e; display: inline-block; min-width: 3em; }}
+  .hl {{ background: #49483e; display: block; }}
+  .bug-link {{ font-size: 0.85rem; }}
+  .tags {{ display: block; margin-top: 4px; font-size: 0.75rem; color: #7f8c8d; }}
+  .tags a {{ color: #2980b9; }}
+  .fix code {{ background: #eef; padding: 0 4px; }}
+  details ol {{ margin: 4px 0 0; padding-left: 1.5rem; font-size: 0.85rem; }}
+  .meta {{ color: #7f8c8d; font-size: 0.8rem; margin: -0.5rem 0 0; }}
+  .hidden {{ display: none; }}
 </style>
 </head>
 <body>
+<div class="layout">
+<aside>
+<h2>Files</h2>
+<ul>
+{sidebar}
+</ul>
+</aside>
+<main>
 <h1>SAST Report: {project}</h1>
+<p class="meta">{meta}</p>
 <div class="summary">
   <span class="count count-total">{total} findings</span>
   <span class="count count-error">{errors} errors</span>
-  <span class="count count-warning">{warnings} warnings</span>
+  <span class="count count-warning">{warnings} warnings</span>{baseline}
+  <span id="shown"></span>
+</div>
+<div class="filters">
+  <label>Tool<select id="f-tool"><option value="">all</option>{tool_options}</select></label>
+  <label>Severity<select id="f-severity"><option value="">all</option>{severity_options}</select></label>
+  <label>Rule<select id="f-rule"><option value="">all</option>{rule_options}</select></label>
+  <label>Path<input id="f-path" type="search" placeholder="substring"></label>
+  <label>Group by<select id="f-group"><option value="">none</option><option value="file">file</option><option value="rule">rule</option></select></label>
+  <button id="f-reset" type="button">Reset</button>
 </div>
-<table>
+<table id="findings">
 <thead>
-<tr><th>Tool</th><th>Severity</th><th>File</th><th>Line</th><th>Rule</th><th>Message</th></tr>
+<tr><th data-key="tool">Tool</th><th data-key="rank">Severity</th><th data-key="file">File</th><th data-key="line">Line</th><th data-key="rule">Rule</th><th>Message</th></tr>
 </thead>
 <tbody>
 {rows}
 </tbody>
 </table>
+{fixed}
+</main>
+</div>
+<script>
+(function () {{
+  var tbody = document.querySelector('#findings tbody');
+  var rows = Array.prototype.slice.call(tbody.querySelectorAll('tr.finding'));
+  var inputs = ['tool', 'severity', 'rule', 'path', 'group'].reduce(function (acc, k) {{
+    acc[k] = document.getElementById('f-' + k);
+    return acc;
+  }}, {{}});
+  var sortKey = null, sortDir = 1;
+
+  function value(row, key) {{
+    var v = row.dataset[key] || '';
+    return (key === 'line' || key === 'rank') ? Number(v) : v;
+  }}
+
+  function render() {{
+    var tool = inputs.tool.value, sev = inputs.severity.value, rule = inputs.rule.value;
+    var path = inputs.path.value.toLowerCase(), group = inputs.group.value;
+    var visible = rows.filter(function (r) {{
+      return (!tool || r.dataset.tool === tool) &&
+        (!sev || r.dataset.severity === sev) &&
+        (!rule || r.dataset.rule === rule) &&
+        (!path || r.dataset.file.toLowerCase().indexOf(path) !== -1);
+    }});
+    var ordered = visible.slice();
+    ordered.sort(function (a, b) {{
+      if (group) {{
+        var ga = value(a, group), gb = value(b, group);
+        if (ga !== gb) return ga < gb ? -1 : 1;
+      }}
+      if (sortKey) {{
+        var va = value(a, sortKey), vb = value(b, sortKey);
+        if (va !== vb) return (va < vb ? -1 : 1) * sortDir;
+      }}
+      return rows.indexOf(a) - rows.indexOf(b);
+    }});
+
+    while (tbody.firstChild) tbody.removeChild(tbody.firstChild);
+    rows.forEach(function (r) {{ r.classList.add('hidden'); }});
+    var current = null;
+    ordered.forEach(function (r) {{
+      if (group && value(r, group) !== current) {{
+        current = value(r, group);
+        var count = ordered.filter(function (o) {{ return value(o, group) === current; }}).length;
+        var tr = document.createElement('tr');
+        tr.className = 'group';
+        var td = document.createElement('td');
+        td.colSpan = 6;
+        td.textContent = (current || '-') + ' (' + count + ')';
+        tr.appendChild(td);
+        tbody.appendChild(tr);
+      }}
+      r.classList.remove('hidden');
+      tbody.appendChild(r);
+    }});
+    rows.forEach(function (r) {{ if (r.classList.contains('hidden')) tbody.appendChild(r); }});
+    document.getElementById('shown').textContent =
+      visible.length === rows.length ? '' : visible.length + ' shown';
+  }}
+
+  Object.keys(inputs).forEach(function (k) {{
+    inputs[k].addEventListener('input', render);
+  }});
+  document.getElementById('f-reset').addEventListener('click', function () {{
+    Object.keys(inputs).forEach(function (k) {{ inputs[k].value = ''; }});
+    render();
+  }});
+  document.querySelectorAll('th[data-key]').forEach(function (th) {{
+    th.addEventListener('click', function () {{
+      var key = th.dataset.key;
+      sortDir = sortKey === key ? -sortDir : (key === 'rank' ? -1 : 1);
+      sortKey = key;
+      document.querySelectorAll('th').forEach(function (h) {{ h.classList.remove('sorted-asc', 'sorted-desc'); }});
+      th.classList.add(sortDir > 0 ? 'sorted-asc' : 'sorted-desc');
+      render();
+    }});
+  }});
+  document.addEventListener('click', function (e) {{
+    var link = e.target.closest ? e.target.closest('.file-link') : null;
+    if (!link) return;
+    e.preventDefault();
+    var row = link.closest('tr');
+    inputs.path.value = link.dataset.file || (row && row.dataset.file) || '';
+    render();
+  }});
+}})();
+</script>
 </body>
 </html>"#,
-        project = html_escape(project_name),
+        project = html_escape(report.project_name),
+        meta = report
+            .metadata
+            .summary()
+            .iter()
+            .map(|(key, value)| format!("<b>{key}</b> {}", html_escape(value)))
+            .collect::<Vec<_>>()
+            .join(" &middot; "),
         total = findings.len(),
         errors = findings
             .iter()
-            .filter(|f| matches!(f.severity, crate::model::Severity::Error))
+            .filter(|f| matches!(f.severity, Severity::Error))
             .count(),
         warnings = findings
             .iter()
-            .filter(|f| matches!(f.severity, crate::model::Severity::Warning))
+            .filter(|f| matches!(f.severity, Severity::Warning))
             .count(),
+        sidebar = sidebar,
+        tool_options = tool_options,
+        severity_options = severity_options,
+        rule_options = rule_options,
         rows = rows,
+        baseline = report
+            .metadata
+            .baseline
+            .as_ref()
+            .map(|b| format!(
+                "\n  <span class=\"count count-total\">{} new</span>\n  <span class=\"count count-total\">{} fixed</span>",
+                b.new, b.fixed
+            ))
+            .unwrap_or_default(),
+        fixed = fixed_section(report),
+    );
+
+    emit(report, OutputFormat::Html, "report.html", html).await
+}
+
+/// Findings of the compared report that are gone, after the current ones.
+fn fixed_section(report: &Report<'_>) -> String {
+    if report.fixed.is_empty() {
+        return String::new();
+    }
+    let mut out = format!(
+        "<h2>Fixed ({})</h2>\n<table id=\"fixed\">\n<thead>\n<tr><th>Tool</th><th>Severity</th><th>File</th><th>Line</th><th>Rule</th><th>Message</th></tr>\n</thead>\n<tbody>\n",
+        report.fixed.len()
     );
+    for f in report.fixed {
+        let _ = writeln!(
+            out,
+            "<tr class=\"fixed severity-{sev}\"><td>{}</td><td>{sev}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            html_escape(&f.tool),
+            html_escape(&display_path(&f.location.file, report.project_path)),
+            f.location.line.map(|l| l.to_string()).unwrap_or_else(|| "-".to_string()),
+            html_escape(f.rule_id.as_deref().unwrap_or("-")),
+            html_escape(&f.message),
+            sev = f.severity,
+        );
+    }
+    out.push_str("</tbody>\n</table>");
+    out
+}
+
+/// Escaped source lines around `line`, with the line itself highlighted.
+fn snippet(sources: &mut SourceCache, file: &Path, line: usize) -> Option<String> {
+    let lines = sources.lines(file)?;
+    if line == 0 || line > lines.len() {
+        return None;
+    }
+    let start = line.saturating_sub(CONTEXT_LINES).max(1);
+    let end = (line + CONTEXT_LINES).min(lines.len());
+
+    let mut out = String::new();
+    for n in start..=end {
+        let text = format!(
+            "<span class=\"ln\">{n}</span>{}",
+            html_escape(&lines[n - 1])
+        );
+        if n == line {
+            let _ = write!(out, "<span class=\"hl\">{text}</span>");
+        } else {
+            let _ = writeln!(out, "{text}");
+        }
+    }
+    Some(out)
+}
+
+/// Comparison state, CWE, OWASP, confidence and rule documentation, on one
+/// line.
+fn tags(f: &Finding) -> String {
+    let mut tags: Vec<String> = Vec::new();
+    match f.baseline_state {
+        Some(BaselineState::New) => tags.push("<strong>new</strong>".to_string()),
+        Some(state) => tags.push(state.name().to_string()),
+        None => {}
+    }
+    match (f.kind, f.security_severity) {
+        (_, Some(security_severity)) => {
+            tags.push(format!("{} vulnerability", security_severity.name()))
+        }
+        (Some(kind), None) => tags.push(kind.name().to_string()),
+        (None, None) => {}
+    }
+    tags.extend(f.cwe.iter().map(|c| {
+        format!("<a href=\"https://cwe.mitre.org/data/definitions/{c}.html\">CWE-{c}</a>")
+    }));
+    tags.extend(f.owasp.iter().map(|o| html_escape(o)));
+    if let Some(confidence) = f.confidence {
+        tags.push(format!("{} confidence", confidence.name()));
+    }
+    for c in &f.correlated {
+        tags.push(format!(
+            "also {} <code>{}</code>",
+            html_escape(&c.tool),
+            html_escape(c.rule_id.as_deref().unwrap_or("-"))
+        ));
+    }
+    if let Some(uri) = &f.help_uri {
+        tags.push(format!("<a href=\"{}\">rule help</a>", html_escape(uri)));
+    }
+    if tags.is_empty() {
+        return String::new();
+    }
+    format!("<span class=\"tags\">{}</span>", tags.join(" · "))
+}
 
-    let path = output_dir.join("report.html");
-    tokio::fs::write(&path, html).await?;
-    eprintln!("[sast] HTML report saved to {}", path.display());
-    Ok(())
+fn steps_list(steps: &[RelatedLocation], project_path: &Path) -> String {
+    steps
+        .iter()
+        .map(|step| {
+            let line = step
+                .location
+                .line
+                .map(|l| format!(":{l}"))
+                .unwrap_or_default();
+            format!(
+                "<li><code>{}{line}</code> {}</li>",
+                html_escape(&display_path(&step.location.file, project_path)),
+                html_escape(step.message.as_deref().unwrap_or(""))
+            )
+        })
+        .collect()
 }
 
-fn html_escape(s: &str) -> String {
-    s.replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        .replace('"', "&quot;")
+/// A per-bug HTML page written by scan-build into the output directory.
+struct BugPage {
+    href: String,
+    file: Option<String>,
+    line: Option<u32>,
+    description: Option<String>,
+}
+
+impl BugPage {
+    fn matches(&self, f: &Finding) -> bool {
+        let same_file = match &self.file {
+            Some(bug_file) => {
+                let finding_file = f.location.file.to_string_lossy().replace('\\', "/");
+                bug_file.ends_with(&finding_file) || finding_file.ends_with(bug_file.as_str())
+            }
+            None => false,
+        };
+        same_file && self.line.is_some() && self.line == f.location.line
+    }
+
+    fn label(&self) -> String {
+        let file = self
+            .file
+            .as_deref()
+            .and_then(|f| f.rsplit('/').next())
+            .unwrap_or("?");
+        let line = self.line.map(|l| format!(":{l}")).unwrap_or_default();
+        match &self.description {
+            Some(desc) => format!("{file}{line} — {desc}"),
+            None => format!("{file}{line}"),
+        }
+    }
+}
+
+/// Find scan-build's `report-*.html` pages below the output directory and
+/// read the BUGFILE/BUGLINE/BUGDESC comments it embeds in each.
+fn scan_build_pages(output_dir: &Path) -> Vec<BugPage> {
+    // `sast diff` to stdout has no output directory; don't glob from `/`
+    if output_dir.as_os_str().is_empty() {
+        return Vec::new();
+    }
+    let pattern = format!("{}/**/report-*.html", output_dir.display());
+    let Ok(paths) = glob::glob(&pattern) else {
+        return Vec::new();
+    };
+
+    let comment = |text: &str, key: &str| -> Option<String> {
+        let start = text.find(&format!("<!-- {key} "))? + key.len() + 6;
+        let end = text[start..].find("-->")? + start;
+        Some(text[start..end].trim().to_string())
+    };
+
+    let mut pages: Vec<BugPage> = paths
+        .flatten()
+        .filter_map(|path| {
+            let text = std::fs::read_to_string(&path).ok()?;
+            let href = path
+                .strip_prefix(output_dir)
+                .ok()?
+                .to_string_lossy()
+                .replace('\\', "/");
+            Some(BugPage {
+                href,
+                file: comment(&text, "BUGFILE").map(|f| f.replace('\\', "/")),
+                line: comment(&text, "BUGLINE").and_then(|l| l.parse().ok()),
+                description: comment(&text, "BUGDESC"),
+            })
+        })
+        .collect();
+    pages.sort_by(|a, b| a.href.cmp(&b.href));
+    pages
 }
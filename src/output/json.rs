@@ -1,11 +1,15 @@
-use crate::model::Finding;
+use super::{emit, OutputFormat, Report};
 use anyhow::Result;
-use std::path::Path;
+use serde_json::json;
 
-pub async fn write(findings: &[Finding], output_dir: &Path) -> Result<()> {
-    let path = output_dir.join("report.json");
-    let json = serde_json::to_string_pretty(findings)?;
-    tokio::fs::write(&path, json).await?;
-    eprintln!("[sast] JSON report saved to {}", path.display());
-    Ok(())
+pub async fn write(report: &Report<'_>) -> Result<()> {
+    let mut envelope = json!({
+        "metadata": report.metadata,
+        "findings": report.findings
+    });
+    if report.metadata.baseline.is_some() {
+        envelope["fixed"] = serde_json::to_value(report.fixed)?;
+    }
+    let json = serde_json::to_string_pretty(&envelope)?;
+    emit(report, OutputFormat::Json, "report.json", json).await
 }
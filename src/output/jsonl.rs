@@ -0,0 +1,56 @@
+use super::{flat_field, OutputFormat, Report, ReportSink};
+use crate::model::{Finding, FLAT_FIELDS, FLAT_SCHEMA_VERSION};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// `report.jsonl`: one finding per line in the flat layout, written as it
+/// goes so large scans never sit in memory as one document. Fixed findings
+/// of a comparison follow, marked by `baseline_state`.
+pub async fn write(report: &Report<'_>) -> Result<()> {
+    let mut stream = Stream::create(report).await?;
+    stream.write(report.findings).await?;
+    stream.write(report.fixed).await?;
+    stream.finish().await
+}
+
+/// `report.jsonl` opened before the tools run, so a scan can write each
+/// tool's findings once they are parsed. Only findings that later steps
+/// won't change (no correlation, no comparison) can go out this early.
+pub struct Stream {
+    sink: ReportSink,
+    project_path: PathBuf,
+}
+
+impl Stream {
+    pub async fn create(report: &Report<'_>) -> Result<Self> {
+        Ok(Self {
+            sink: ReportSink::create(report, OutputFormat::Jsonl, "report.jsonl").await?,
+            project_path: report.project_path.to_path_buf(),
+        })
+    }
+
+    /// Append `findings`; they are readable once this returns.
+    pub async fn write(&mut self, findings: &[Finding]) -> Result<()> {
+        for f in findings {
+            self.sink
+                .write(line(f, &self.project_path).as_bytes())
+                .await?;
+        }
+        self.sink.flush().await
+    }
+
+    pub async fn finish(self) -> Result<()> {
+        self.sink.finish().await
+    }
+}
+
+fn line(f: &Finding, project_path: &Path) -> String {
+    // Built by hand to keep fields in layout order
+    let mut line = format!("{{\"schema_version\":{FLAT_SCHEMA_VERSION}");
+    for field in FLAT_FIELDS {
+        let value = flat_field(f, field, project_path).unwrap_or_default();
+        line.push_str(&format!(",\"{field}\":{value}"));
+    }
+    line.push_str("}\n");
+    line
+}
@@ -0,0 +1,227 @@
+use super::{display_path, emit, xml_escape, OutputFormat, Report};
+use crate::config::JunitTestcase;
+use crate::model::{BaselineState, Finding, Severity};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+pub async fn write(report: &Report<'_>) -> Result<()> {
+    let xml = render(report);
+    emit(report, OutputFormat::Junit, "junit.xml", xml).await
+}
+
+/// One testsuite per tool, one testcase per rule (or file). A testcase fails
+/// when it holds a finding at or above the configured severity; tools that
+/// could not run get a single errored testcase. Fixed findings of a
+/// comparison never fail theirs.
+fn render(report: &Report<'_>) -> String {
+    let options = &report.options.junit;
+    let min_rank = options.min_severity.unwrap_or(Severity::Warning).rank();
+    let by_file = options.testcase == Some(JunitTestcase::File);
+
+    // tool -> testcase -> findings
+    let mut suites: BTreeMap<&str, BTreeMap<String, Vec<&Finding>>> = BTreeMap::new();
+    for run in report.runs {
+        suites.entry(run.tool_name.as_str()).or_default();
+    }
+    for f in report.findings.iter().chain(report.fixed) {
+        let case = if by_file {
+            display_path(&f.location.file, report.project_path)
+        } else {
+            f.rule_id.clone().unwrap_or_else(|| f.tool.clone())
+        };
+        suites
+            .entry(f.tool.as_str())
+            .or_default()
+            .entry(case)
+            .or_default()
+            .push(f);
+    }
+
+    // Scan metadata, repeated in each suite as JUnit only has per-suite properties
+    let mut properties = String::from("    <properties>\n");
+    for (key, value) in report.metadata.summary() {
+        let name = match key {
+            "sast" => "version",
+            other => other,
+        };
+        let _ = writeln!(
+            properties,
+            "      <property name=\"sast.{name}\" value=\"{}\"/>",
+            xml_escape(&value)
+        );
+    }
+    properties.push_str("    </properties>\n");
+
+    let mut body = String::new();
+    let (mut total_tests, mut total_failures) = (0, 0);
+    let mut total_time = 0.0;
+    for (tool, cases) in &suites {
+        let run = report.runs.iter().find(|r| r.tool_name == *tool);
+        let time = run.map(|r| r.duration.as_secs_f64()).unwrap_or(0.0);
+        let failures = cases
+            .values()
+            .filter(|fs| fs.iter().any(|f| fails(f, min_rank)))
+            .count();
+        total_tests += cases.len();
+        total_failures += failures;
+        total_time += time;
+
+        let _ = write!(
+            body,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"0\" time=\"{:.3}\"",
+            xml_escape(tool),
+            cases.len(),
+            failures,
+            time
+        );
+        if let Some(run) = run {
+            let _ = write!(
+                body,
+                " timestamp=\"{}\"",
+                run.started_at.format("%Y-%m-%dT%H:%M:%S")
+            );
+        }
+        let _ = writeln!(body, ">");
+        let _ = write!(body, "{properties}");
+        for (case, findings) in cases {
+            testcase(&mut body, tool, case, findings, min_rank, report);
+        }
+        let _ = writeln!(body, "  </testsuite>");
+    }
+
+    for failed in report.failed_tools {
+        let _ = writeln!(
+            body,
+            "  <testsuite name=\"{}\" tests=\"1\" failures=\"0\" errors=\"1\" skipped=\"0\" time=\"0.000\">",
+            xml_escape(&failed.name)
+        );
+        let _ = write!(body, "{properties}");
+        let _ = writeln!(
+            body,
+            "    <testcase name=\"execution\" classname=\"{0}\" time=\"0.000\">\n      <error message=\"{1}\" type=\"ExecutionError\">{1}</error>\n    </testcase>",
+            xml_escape(&failed.name),
+            xml_escape(&failed.error)
+        );
+        let _ = writeln!(body, "  </testsuite>");
+    }
+    total_tests += report.failed_tools.len();
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        out,
+        "<testsuites name=\"sast: {}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">",
+        xml_escape(report.project_name),
+        total_tests,
+        total_failures,
+        report.failed_tools.len(),
+        total_time
+    );
+    out.push_str(&body);
+    out.push_str("</testsuites>\n");
+    out
+}
+
+fn testcase(
+    out: &mut String,
+    tool: &str,
+    case: &str,
+    findings: &[&Finding],
+    min_rank: u8,
+    report: &Report<'_>,
+) {
+    let _ = writeln!(
+        out,
+        "    <testcase name=\"{}\" classname=\"{}\" time=\"0.000\">",
+        xml_escape(case),
+        xml_escape(tool)
+    );
+    // How the testcase's findings compare with the earlier report
+    if report.metadata.baseline.is_some() {
+        out.push_str("      <properties>\n");
+        for state in [
+            BaselineState::New,
+            BaselineState::Unchanged,
+            BaselineState::Fixed,
+        ] {
+            let count = findings
+                .iter()
+                .filter(|f| f.baseline_state == Some(state))
+                .count();
+            let _ = writeln!(
+                out,
+                "        <property name=\"sast.{}\" value=\"{count}\"/>",
+                state.name()
+            );
+        }
+        out.push_str("      </properties>\n");
+    }
+
+    let (failing, passing): (Vec<&Finding>, Vec<&Finding>) =
+        findings.iter().partition(|f| fails(f, min_rank));
+
+    if let Some(worst) = failing.iter().map(|f| f.severity).max_by_key(|s| s.rank()) {
+        let message = if failing.len() == 1 {
+            failing[0].message.clone()
+        } else {
+            format!("{} findings", failing.len())
+        };
+        let _ = writeln!(
+            out,
+            "      <failure message=\"{}\" type=\"{}\">{}</failure>",
+            xml_escape(&message),
+            worst,
+            xml_escape(&describe(&failing, report))
+        );
+    }
+    // Findings below the threshold are kept visible without failing the test
+    if !passing.is_empty() {
+        let _ = writeln!(
+            out,
+            "      <system-out>{}</system-out>",
+            xml_escape(&describe(&passing, report))
+        );
+    }
+    let _ = writeln!(out, "    </testcase>");
+}
+
+fn fails(f: &Finding, min_rank: u8) -> bool {
+    f.severity.rank() >= min_rank && f.baseline_state != Some(BaselineState::Fixed)
+}
+
+/// `path:line:column: severity: message [rule] CWE-n`, then the snippet
+/// indented below it. Findings of a comparison start with their state,
+/// e.g. `[new] `.
+fn describe(findings: &[&Finding], report: &Report<'_>) -> String {
+    let mut text = String::new();
+    for f in findings {
+        if let Some(state) = f.baseline_state {
+            let _ = write!(text, "[{}] ", state.name());
+        }
+        let _ = write!(
+            text,
+            "{}",
+            display_path(&f.location.file, report.project_path)
+        );
+        if let Some(line) = f.location.line {
+            let _ = write!(text, ":{line}");
+            if let Some(column) = f.location.column {
+                let _ = write!(text, ":{column}");
+            }
+        }
+        let _ = write!(text, ": {}: {}", f.severity, f.message);
+        if let Some(rule) = &f.rule_id {
+            let _ = write!(text, " [{rule}]");
+        }
+        for cwe in &f.cwe {
+            let _ = write!(text, " CWE-{cwe}");
+        }
+        text.push('\n');
+        if let Some(snippet) = &f.snippet {
+            for line in snippet.lines() {
+                let _ = writeln!(text, "    {line}");
+            }
+        }
+    }
+    text
+}
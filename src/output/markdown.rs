@@ -0,0 +1,251 @@
+use super::{display_path, emit, html_escape, percent_encode, OutputFormat, Report};
+use crate::model::{BaselineState, Finding, Severity};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+/// GitHub rejects comments over 65536 characters; leave room for a header
+const DEFAULT_MAX_BYTES: usize = 60_000;
+
+pub async fn write(report: &Report<'_>) -> Result<()> {
+    let markdown = render(report);
+    emit(report, OutputFormat::Markdown, "report.md", markdown).await
+}
+
+fn render(report: &Report<'_>) -> String {
+    let findings = report.findings;
+    let options = &report.options.markdown;
+    let max_bytes = options.max_bytes.unwrap_or(DEFAULT_MAX_BYTES);
+
+    let mut out = String::new();
+    let _ = writeln!(out, "## SAST Report: {}\n", escape(report.project_name));
+
+    if findings.is_empty() {
+        let _ = writeln!(out, "No findings.\n");
+        out.push_str(&fixed_section(report, max_bytes));
+        out.push_str(&footer(report));
+        return out;
+    }
+
+    // Summary table: severity rows, tool columns
+    let mut tools: Vec<&str> = findings.iter().map(|f| f.tool.as_str()).collect();
+    tools.sort();
+    tools.dedup();
+    let mut counts: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    for f in findings {
+        *counts
+            .entry((f.severity.name(), f.tool.as_str()))
+            .or_default() += 1;
+    }
+
+    let _ = write!(out, "**{} findings**", findings.len());
+    if let Some(baseline) = &report.metadata.baseline {
+        let _ = write!(out, " ({} new, {} fixed)", baseline.new, baseline.fixed);
+    }
+    out.push_str("\n\n");
+    let _ = writeln!(out, "| Severity | {} | Total |", tools.join(" | "));
+    let _ = writeln!(out, "|---|{}---:|", "---:|".repeat(tools.len()));
+    for severity in Severity::ALL {
+        let total: usize = tools
+            .iter()
+            .map(|t| counts.get(&(severity.name(), *t)).copied().unwrap_or(0))
+            .sum();
+        if total == 0 {
+            continue;
+        }
+        let cells: Vec<String> = tools
+            .iter()
+            .map(|t| {
+                counts
+                    .get(&(severity.name(), *t))
+                    .copied()
+                    .unwrap_or(0)
+                    .to_string()
+            })
+            .collect();
+        let _ = writeln!(out, "| {} | {} | {} |", severity, cells.join(" | "), total);
+    }
+    out.push('\n');
+
+    // One collapsible section per file, most severe files first
+    let mut by_file: BTreeMap<String, Vec<&Finding>> = BTreeMap::new();
+    for f in findings {
+        by_file
+            .entry(display_path(&f.location.file, report.project_path))
+            .or_default()
+            .push(f);
+    }
+    let mut files: Vec<(String, Vec<&Finding>)> = by_file.into_iter().collect();
+    files.sort_by_key(|(_, fs)| std::cmp::Reverse(fs.iter().map(|f| f.severity.rank()).max()));
+    for (_, file_findings) in &mut files {
+        file_findings.sort_by_key(|f| (std::cmp::Reverse(f.severity.rank()), f.location.line));
+    }
+
+    // Keep room for the trailer, fixed findings and footer when the report
+    // is truncated
+    let footer = footer(report);
+    let fixed = fixed_section(report, max_bytes);
+    let budget = max_bytes.saturating_sub(200 + fixed.len() + footer.len());
+    let mut shown = 0;
+    'files: for (file, file_findings) in &files {
+        let mut section = String::new();
+        let _ = writeln!(
+            section,
+            "<details>\n<summary><code>{}</code> — {} finding{}</summary>\n",
+            html_escape(file),
+            file_findings.len(),
+            if file_findings.len() == 1 { "" } else { "s" }
+        );
+        let _ = writeln!(section, "| Line | Severity | Tool | Rule | Message |");
+        let _ = writeln!(section, "|---:|---|---|---|---|");
+        let closing = "\n</details>\n\n";
+
+        for (rows, f) in file_findings.iter().enumerate() {
+            let row = row(f, file, options.link_template.as_deref());
+            if out.len() + section.len() + row.len() + closing.len() > budget {
+                if rows > 0 {
+                    section.push_str(closing);
+                    out.push_str(&section);
+                }
+                break 'files;
+            }
+            section.push_str(&row);
+            shown += 1;
+        }
+        section.push_str(closing);
+        out.push_str(&section);
+    }
+
+    let hidden = findings.len() - shown;
+    if hidden > 0 {
+        let _ = writeln!(
+            out,
+            "_… and {hidden} more finding{} not shown (report size limit)._",
+            if hidden == 1 { "" } else { "s" }
+        );
+        out.push('\n');
+    }
+    out.push_str(&fixed);
+    out.push_str(&footer);
+    out
+}
+
+/// Findings of the compared report that are gone, collapsed and kept to a
+/// quarter of `max_bytes`.
+fn fixed_section(report: &Report<'_>, max_bytes: usize) -> String {
+    if report.fixed.is_empty() {
+        return String::new();
+    }
+    let mut section = format!(
+        "<details>\n<summary>Fixed — {} finding{}</summary>\n\n",
+        report.fixed.len(),
+        if report.fixed.len() == 1 { "" } else { "s" }
+    );
+    let _ = writeln!(
+        section,
+        "| File | Line | Severity | Tool | Rule | Message |"
+    );
+    let _ = writeln!(section, "|---|---:|---|---|---|---|");
+    for (shown, f) in report.fixed.iter().enumerate() {
+        let row = format!(
+            "| <code>{}</code> | {} | {} | {} | {} | {} |\n",
+            html_escape(&display_path(&f.location.file, report.project_path)),
+            f.location.line.map_or("-".to_string(), |l| l.to_string()),
+            f.severity,
+            escape(&f.tool),
+            f.rule_id
+                .as_deref()
+                .map_or("-".to_string(), |r| format!("`{}`", r.replace('`', ""))),
+            escape(&f.message)
+        );
+        if section.len() + row.len() > max_bytes / 4 {
+            let _ = writeln!(
+                section,
+                "\n_… and {} more not shown (report size limit)._",
+                report.fixed.len() - shown
+            );
+            break;
+        }
+        section.push_str(&row);
+    }
+    section.push_str("\n</details>\n\n");
+    section
+}
+
+/// One small line recording how the report was produced.
+fn footer(report: &Report<'_>) -> String {
+    let items: Vec<String> = report
+        .metadata
+        .summary()
+        .iter()
+        .map(|(key, value)| format!("{key} {}", escape(value)))
+        .collect();
+    format!("<sub>{}</sub>\n", items.join(" · "))
+}
+
+fn row(f: &Finding, file: &str, link_template: Option<&str>) -> String {
+    let line = match (f.location.line, link_template) {
+        (Some(line), Some(template)) => format!(
+            "[{line}]({})",
+            template
+                .replace("{path}", &url_path(file))
+                .replace("{line}", &line.to_string())
+        ),
+        (Some(line), None) => line.to_string(),
+        (None, _) => "-".to_string(),
+    };
+    let rule = match (f.rule_id.as_deref(), &f.help_uri) {
+        (Some(r), Some(uri)) => format!("[{}]({uri})", code_span(r)),
+        (Some(r), None) => code_span(r),
+        (None, _) => "-".to_string(),
+    };
+    let mut message = escape(&f.message);
+    if f.baseline_state == Some(BaselineState::New) {
+        message.insert_str(0, "**New:** ");
+    }
+    if !f.cwe.is_empty() {
+        let cwe: Vec<String> = f.cwe.iter().map(|c| format!("CWE-{c}")).collect();
+        let _ = write!(message, " ({})", cwe.join(", "));
+    }
+    if let Some(fix) = &f.fix {
+        let _ = write!(message, "<br>Fix: {}", code_span(&fix.replacement));
+    }
+
+    format!(
+        "| {} | {} | {} | {} | {} |\n",
+        line,
+        f.severity,
+        escape(&f.tools().join(", ")),
+        rule,
+        message
+    )
+}
+
+/// Make text safe inside a table cell: one line, no column breaks, no HTML.
+fn escape(s: &str) -> String {
+    html_escape(&s.split_whitespace().collect::<Vec<_>>().join(" ")).replace('|', "\\|")
+}
+
+/// Code span for a table cell. Its text is shown as is, so only pipes
+/// (column breaks) and backticks need care: the fence is one backtick longer
+/// than any run inside.
+fn code_span(s: &str) -> String {
+    let text = s.replace(['\r', '\n'], " ").replace('|', "\\|");
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest + 1);
+    // A space keeps a leading or trailing backtick from joining the fence
+    let pad = if text.starts_with('`') || text.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+    format!("{fence}{pad}{text}{pad}{fence}")
+}
+
+/// A project-relative path for a link, each segment percent-encoded.
+fn url_path(file: &str) -> String {
+    file.split('/')
+        .map(percent_encode)
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -1,14 +1,25 @@
+pub mod checkstyle;
+pub mod csv;
+pub mod gitlab;
 pub mod html;
 pub mod json;
+pub mod jsonl;
+pub mod junit;
+pub mod markdown;
 pub mod native;
+pub mod pretty;
 pub mod sarif;
+pub mod sonar;
+pub mod template;
 
-use crate::config::ToolConfig;
+use crate::config::{ReportConfig, ToolConfig};
+use crate::metadata::ScanMetadata;
 use crate::model::Finding;
-use crate::runner::ToolRun;
-use anyhow::Result;
+use crate::runner::{FailedTool, ToolRun};
+use anyhow::{ensure, Context, Result};
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OutputFormat {
@@ -16,6 +27,16 @@ pub enum OutputFormat {
     Sarif,
     Html,
     Json,
+    Markdown,
+    GitlabSast,
+    GitlabCodeQuality,
+    Junit,
+    Checkstyle,
+    Sonar,
+    Csv,
+    Jsonl,
+    Pretty,
+    Template,
 }
 
 impl OutputFormat {
@@ -25,23 +46,387 @@ impl OutputFormat {
             "sarif" => Ok(Self::Sarif),
             "html" => Ok(Self::Html),
             "json" => Ok(Self::Json),
-            _ => anyhow::bail!("Unknown format '{}'. Use: native, sarif, html, json", s),
+            "markdown" | "md" => Ok(Self::Markdown),
+            "gitlab-sast" => Ok(Self::GitlabSast),
+            "gitlab-codequality" | "codequality" => Ok(Self::GitlabCodeQuality),
+            "junit" => Ok(Self::Junit),
+            "checkstyle" => Ok(Self::Checkstyle),
+            "sonar" | "sonarqube" => Ok(Self::Sonar),
+            "csv" => Ok(Self::Csv),
+            "jsonl" | "ndjson" => Ok(Self::Jsonl),
+            "pretty" | "text" => Ok(Self::Pretty),
+            "template" => Ok(Self::Template),
+            _ => anyhow::bail!(
+                "Unknown format '{}'. Use: native, sarif, html, json, markdown, gitlab-sast, gitlab-codequality, junit, checkstyle, sonar, csv, jsonl, pretty, template",
+                s
+            ),
         }
     }
+
+    /// Parse a list of format names (each may itself be comma-separated),
+    /// dropping duplicates while keeping the requested order.
+    pub fn parse_list(names: &[String]) -> Result<Vec<Self>> {
+        let mut formats = Vec::new();
+        for name in names.iter().flat_map(|n| n.split(',')) {
+            let format = Self::from_str(name.trim())?;
+            if !formats.contains(&format) {
+                formats.push(format);
+            }
+        }
+        anyhow::ensure!(!formats.is_empty(), "No output format given");
+        Ok(formats)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Native => "native",
+            Self::Sarif => "sarif",
+            Self::Html => "html",
+            Self::Json => "json",
+            Self::Markdown => "markdown",
+            Self::GitlabSast => "gitlab-sast",
+            Self::GitlabCodeQuality => "gitlab-codequality",
+            Self::Junit => "junit",
+            Self::Checkstyle => "checkstyle",
+            Self::Sonar => "sonar",
+            Self::Csv => "csv",
+            Self::Jsonl => "jsonl",
+            Self::Pretty => "pretty",
+            Self::Template => "template",
+        }
+    }
+
+    /// Whether the format can show a comparison: fixed findings next to the
+    /// current ones, which carry their state. The others take only findings
+    /// that are there (GitLab and Sonar compare reports themselves).
+    pub fn shows_comparison(&self) -> bool {
+        !matches!(
+            self,
+            Self::Native
+                | Self::GitlabSast
+                | Self::GitlabCodeQuality
+                | Self::Checkstyle
+                | Self::Sonar
+        )
+    }
+
+    /// How the written report is announced on stderr.
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Native => "Native outputs",
+            Self::Sarif => "SARIF report",
+            Self::Html => "HTML report",
+            Self::Json => "JSON report",
+            Self::Markdown => "Markdown report",
+            Self::GitlabSast => "GitLab SAST report",
+            Self::GitlabCodeQuality => "GitLab Code Quality report",
+            Self::Junit => "JUnit report",
+            Self::Checkstyle => "Checkstyle report",
+            Self::Sonar => "Sonar report",
+            Self::Csv => "CSV report",
+            Self::Jsonl => "JSON Lines report",
+            Self::Pretty => "Console report",
+            Self::Template => "Template report",
+        }
+    }
+}
+
+/// Everything the writers know about one scan.
+pub struct Report<'a> {
+    pub runs: &'a [ToolRun],
+    /// Tools that could not be executed
+    pub failed_tools: &'a [FailedTool],
+    pub metadata: &'a ScanMetadata,
+    pub findings: &'a [Finding],
+    /// Findings of the compared report that are gone; see [`crate::baseline`]
+    pub fixed: &'a [Finding],
+    pub output_dir: &'a Path,
+    pub project_name: &'a str,
+    pub project_path: &'a Path,
+    pub tool_configs: &'a HashMap<String, ToolConfig>,
+    pub options: &'a ReportConfig,
+    /// Write the (single) report to stdout instead of the output directory
+    pub to_stdout: bool,
+}
+
+/// Write every requested format from the same runs and findings.
+pub async fn write_output(formats: &[OutputFormat], report: &Report<'_>) -> Result<()> {
+    for name in report.options.file_names.keys() {
+        OutputFormat::from_str(name).context("Invalid key in `report.file_names`")?;
+    }
+    // A report already streamed to stdout leaves no format here
+    if report.to_stdout && !formats.is_empty() {
+        check_stdout(formats)?;
+    }
+
+    for format in formats {
+        match format {
+            OutputFormat::Native => {
+                native::write(report.runs, report.output_dir, report.tool_configs).await?
+            }
+            OutputFormat::Sarif => sarif::write(report).await?,
+            OutputFormat::Html => html::write(report).await?,
+            OutputFormat::Json => json::write(report).await?,
+            OutputFormat::Markdown => markdown::write(report).await?,
+            OutputFormat::GitlabSast => gitlab::write_sast(report).await?,
+            OutputFormat::GitlabCodeQuality => gitlab::write_code_quality(report).await?,
+            OutputFormat::Junit => junit::write(report).await?,
+            OutputFormat::Checkstyle => checkstyle::write(report).await?,
+            OutputFormat::Sonar => sonar::write(report).await?,
+            OutputFormat::Csv => csv::write(report).await?,
+            OutputFormat::Jsonl => jsonl::write(report).await?,
+            OutputFormat::Pretty => pretty::write(report).await?,
+            OutputFormat::Template => template::write(report).await?,
+        }
+    }
+    Ok(())
+}
+
+/// Stdout holds exactly one report, and native outputs are files per tool.
+pub fn check_stdout(formats: &[OutputFormat]) -> Result<()> {
+    ensure!(
+        formats.len() == 1,
+        "Only one format can be written to stdout, got: {}",
+        formats
+            .iter()
+            .map(|f| f.name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    ensure!(
+        formats[0] != OutputFormat::Native,
+        "The native format writes one file per tool and can't go to stdout"
+    );
+    Ok(())
+}
+
+/// Where a report is written: a file in the output directory, or stdout.
+pub struct ReportSink {
+    out: Box<dyn AsyncWrite + Unpin + Send>,
+    path: Option<PathBuf>,
+    label: &'static str,
+}
+
+impl ReportSink {
+    /// Open the destination of `format`. The file is `default_name` unless
+    /// `report.file_names` configures another name for the format.
+    pub async fn create(
+        report: &Report<'_>,
+        format: OutputFormat,
+        default_name: &str,
+    ) -> Result<Self> {
+        if report.to_stdout {
+            return Ok(Self {
+                out: Box::new(tokio::io::BufWriter::new(tokio::io::stdout())),
+                path: None,
+                label: format.label(),
+            });
+        }
+
+        let path = report_path(report, format, default_name);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let file = tokio::fs::File::create(&path)
+            .await
+            .with_context(|| format!("Failed to create '{}'", path.display()))?;
+        Ok(Self {
+            out: Box::new(tokio::io::BufWriter::new(file)),
+            path: Some(path),
+            label: format.label(),
+        })
+    }
+
+    pub async fn write(&mut self, data: &[u8]) -> Result<()> {
+        self.out.write_all(data).await?;
+        Ok(())
+    }
+
+    /// Hand what was written so far to the file or stdout.
+    pub async fn flush(&mut self) -> Result<()> {
+        self.out.flush().await?;
+        Ok(())
+    }
+
+    pub async fn finish(mut self) -> Result<()> {
+        self.out.flush().await?;
+        if let Some(path) = &self.path {
+            eprintln!("[sast] {} saved to {}", self.label, path.display());
+        }
+        Ok(())
+    }
 }
 
-pub async fn write_output(
+/// File `format` is written to outside of stdout mode.
+pub fn report_path(report: &Report<'_>, format: OutputFormat, default_name: &str) -> PathBuf {
+    let name = match report.options.file_names.get(format.name()) {
+        Some(template) => file_name(template, report),
+        None => default_name.to_string(),
+    };
+    report.output_dir.join(name)
+}
+
+/// Write a whole report in one go; see [`ReportSink::create`].
+pub async fn emit(
+    report: &Report<'_>,
     format: OutputFormat,
-    runs: &[ToolRun],
-    findings: &[Finding],
-    output_dir: &Path,
-    project_name: &str,
-    tool_configs: &HashMap<String, ToolConfig>,
+    default_name: &str,
+    content: impl AsRef<[u8]>,
 ) -> Result<()> {
-    match format {
-        OutputFormat::Native => native::write(runs, output_dir, tool_configs).await,
-        OutputFormat::Sarif => sarif::write(findings, output_dir).await,
-        OutputFormat::Html => html::write(findings, output_dir, project_name).await,
-        OutputFormat::Json => json::write(findings, output_dir).await,
+    let mut sink = ReportSink::create(report, format, default_name).await?;
+    sink.write(content.as_ref()).await?;
+    sink.finish().await
+}
+
+/// Expand a configured report file name: `{project}`, `{date}`
+/// (YYYY-MM-DD), `{time}` (HHMMSS), `{commit}` and `{branch}`.
+fn file_name(template: &str, report: &Report<'_>) -> String {
+    let metadata = report.metadata;
+    let git = metadata.git.as_ref();
+    template
+        .replace("{project}", report.project_name)
+        .replace(
+            "{date}",
+            &metadata.started_at.format("%Y-%m-%d").to_string(),
+        )
+        .replace("{time}", &metadata.started_at.format("%H%M%S").to_string())
+        .replace(
+            "{commit}",
+            git.map(|g| &g.commit[..7.min(g.commit.len())])
+                .unwrap_or("nocommit"),
+        )
+        .replace(
+            "{branch}",
+            &git.and_then(|g| g.branch.as_deref())
+                .unwrap_or("nobranch")
+                .replace(['/', '\\'], "-"),
+        )
+}
+
+/// Path shown to readers: relative to the project when possible, with `/`.
+pub fn display_path(file: &Path, project_path: &Path) -> String {
+    relative_to_project(file, project_path)
+        .unwrap_or_else(|| file.to_path_buf())
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// `file` relative to the project root, or `None` when it lies outside it.
+/// Finding paths are already project-relative unless they point outside the
+/// project (see [`crate::paths`]).
+pub fn relative_to_project(file: &Path, project_path: &Path) -> Option<PathBuf> {
+    if file.is_relative() {
+        return Some(file.to_path_buf());
+    }
+    let root = std::fs::canonicalize(project_path).ok()?;
+    let absolute = std::fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf());
+    absolute.strip_prefix(&root).ok().map(Path::to_path_buf)
+}
+
+/// Source files larger than this are not quoted in reports
+const MAX_SOURCE_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Reads each source file once, for writers that quote the code.
+pub struct SourceCache<'a> {
+    project_path: &'a Path,
+    files: HashMap<PathBuf, Option<Vec<String>>>,
+}
+
+impl<'a> SourceCache<'a> {
+    pub fn new(project_path: &'a Path) -> Self {
+        Self {
+            project_path,
+            files: HashMap::new(),
+        }
+    }
+
+    /// Lines of `file`, relative to the project or as given.
+    pub fn lines(&mut self, file: &Path) -> Option<&Vec<String>> {
+        let project_path = self.project_path;
+        self.files
+            .entry(file.to_path_buf())
+            .or_insert_with(|| {
+                [project_path.join(file), file.to_path_buf()]
+                    .iter()
+                    .find(|p| {
+                        std::fs::metadata(p)
+                            .map(|m| m.is_file() && m.len() <= MAX_SOURCE_BYTES)
+                            .unwrap_or(false)
+                    })
+                    .and_then(|p| std::fs::read(p).ok())
+                    .map(|data| {
+                        String::from_utf8_lossy(&data)
+                            .lines()
+                            .map(String::from)
+                            .collect()
+                    })
+            })
+            .as_ref()
+    }
+}
+
+/// One field of the flat finding layout ([`crate::model::FLAT_FIELDS`]),
+/// or `None` for an unknown field name.
+pub fn flat_field(f: &Finding, field: &str, project_path: &Path) -> Option<serde_json::Value> {
+    let value = match field {
+        "tool" => f.tool.clone().into(),
+        "rule_id" => f.rule_id.clone().into(),
+        "severity" => f.severity.name().into(),
+        "file" => display_path(&f.location.file, project_path).into(),
+        "line" => f.location.line.into(),
+        "column" => f.location.column.into(),
+        "message" => f.message.clone().into(),
+        "end_line" => f.location.end_line.into(),
+        "end_column" => f.location.end_column.into(),
+        "cwe" => f.cwe.first().copied().into(),
+        "confidence" => f.confidence.map(|c| c.name()).into(),
+        "help_uri" => f.help_uri.clone().into(),
+        "fingerprint" => f.fingerprint.clone().into(),
+        "tools" => f.tools().join(",").into(),
+        "tool_severity" => f.tool_severity.clone().into(),
+        "security_severity" => f.security_severity.map(|s| s.name()).into(),
+        "kind" => f.kind.map(|k| k.name()).into(),
+        "baseline_state" => f.baseline_state.map(|s| s.name()).into(),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Percent-encode one segment of a URL path.
+pub fn percent_encode(segment: &str) -> String {
+    let mut out = String::new();
+    for b in segment.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~:".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+/// Escape text for HTML content and double-quoted attributes.
+pub fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Escape text for XML content and attributes, dropping characters XML 1.0
+/// can't represent.
+pub fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\n' | '\r' | '\t' => out.push(c),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
     }
+    out
 }
@@ -0,0 +1,244 @@
+use super::{display_path, Report, SourceCache};
+use crate::model::{BaselineState, Finding, Severity};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{IsTerminal, Write as _};
+
+/// Print findings to stdout compiler-style, one code frame each, grouped by
+/// file. Colors are used only on a terminal and when `NO_COLOR` is unset.
+pub async fn write(report: &Report<'_>) -> Result<()> {
+    let stdout = std::io::stdout();
+    let color = stdout.is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty());
+    let text = render(report, Style { color });
+    stdout.lock().write_all(text.as_bytes())?;
+    Ok(())
+}
+
+#[derive(Clone, Copy)]
+struct Style {
+    color: bool,
+}
+
+impl Style {
+    fn paint(&self, code: &str, text: &str) -> String {
+        if self.color {
+            format!("\x1b[{code}m{text}\x1b[0m")
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn severity(&self, severity: Severity) -> String {
+        self.paint(color(severity), severity.name())
+    }
+
+    fn gutter(&self, text: &str) -> String {
+        self.paint("1;34", text)
+    }
+}
+
+fn render(report: &Report<'_>, style: Style) -> String {
+    let mut sources = SourceCache::new(report.project_path);
+    let mut by_file: BTreeMap<String, Vec<&Finding>> = BTreeMap::new();
+    for f in report.findings {
+        by_file
+            .entry(display_path(&f.location.file, report.project_path))
+            .or_default()
+            .push(f);
+    }
+
+    let mut out = String::new();
+    for (file, findings) in &mut by_file {
+        findings.sort_by_key(|f| (f.location.line, f.location.column));
+        for f in findings.iter() {
+            frame(&mut out, f, file, &mut sources, style);
+        }
+    }
+
+    // Gone since the compared report: no code frame, the code moved on
+    for f in report.fixed {
+        let mut location = display_path(&f.location.file, report.project_path);
+        if let Some(line) = f.location.line {
+            let _ = write!(location, ":{line}");
+        }
+        let _ = writeln!(
+            out,
+            "{}{}: {}",
+            style.paint("1;32", "fixed"),
+            style.paint("1", &format!("[{}]", rule(f))),
+            f.message.trim()
+        );
+        let _ = writeln!(out, "  {} {location}\n", style.gutter("-->"));
+    }
+
+    let mut counts: Vec<String> = Vec::new();
+    for severity in Severity::ALL {
+        let n = report
+            .findings
+            .iter()
+            .filter(|f| f.severity == severity)
+            .count();
+        if n > 0 {
+            counts.push(format!("{n} {}", style.severity(severity)));
+        }
+    }
+    let total = report.findings.len();
+    let _ = write!(
+        out,
+        "{}: {} finding{}",
+        style.paint("1", "sast"),
+        total,
+        if total == 1 { "" } else { "s" }
+    );
+    if !counts.is_empty() {
+        let _ = write!(out, " ({})", counts.join(", "));
+    }
+    if let Some(baseline) = &report.metadata.baseline {
+        let _ = write!(out, "; {} new, {} fixed", baseline.new, baseline.fixed);
+    }
+    out.push('\n');
+    out
+}
+
+/// ```text
+/// error[cppcheck/doubleFree]: Memory pointed to by 'arr' is freed twice.
+///   --> memory_issues.cpp:23:5
+///    |
+/// 23 |     free(arr);
+///    |     ^^^^
+/// ```
+fn frame(out: &mut String, f: &Finding, file: &str, sources: &mut SourceCache, style: Style) {
+    let _ = writeln!(
+        out,
+        "{}{}: {}",
+        style.severity(f.severity),
+        style.paint("1", &format!("[{}]", rule(f))),
+        style.paint("1", f.message.trim())
+    );
+
+    let mut location = file.to_string();
+    if let Some(line) = f.location.line {
+        let _ = write!(location, ":{line}");
+        if let Some(column) = f.location.column {
+            let _ = write!(location, ":{column}");
+        }
+    }
+
+    // The tool's own copy of the line stands in when the file isn't here
+    let source_line = f.location.line.filter(|l| *l > 0).and_then(|l| {
+        let text = sources
+            .lines(&f.location.file)
+            .and_then(|lines| lines.get(l as usize - 1))
+            .cloned()
+            .or_else(|| f.snippet.clone().filter(|s| !s.contains('\n')))?;
+        Some((l, text))
+    });
+    let Some((line, text)) = source_line else {
+        let _ = writeln!(out, "  {} {location}", style.gutter("-->"));
+        notes(out, f, " ", style);
+        out.push('\n');
+        return;
+    };
+
+    let number = line.to_string();
+    let pad = " ".repeat(number.len());
+    let _ = writeln!(out, "{pad}{} {location}", style.gutter("-->"));
+    let _ = writeln!(out, "{pad} {}", style.gutter("|"));
+    let _ = writeln!(out, "{} {text}", style.gutter(&format!("{number} |")));
+    if let Some(column) = f.location.column.filter(|c| *c > 0) {
+        // Underline the reported range when it stays on this line
+        let end = f
+            .location
+            .end_column
+            .filter(|_| f.location.end_line.unwrap_or(line) == line);
+        let _ = writeln!(
+            out,
+            "{pad} {} {}",
+            style.gutter("|"),
+            caret(&text, column as usize, end, f.severity, style)
+        );
+    }
+    let _ = writeln!(out, "{pad} {}", style.gutter("|"));
+    notes(out, f, &pad, style);
+    out.push('\n');
+}
+
+/// `tool/rule`, or the tool alone
+fn rule(f: &Finding) -> String {
+    match &f.rule_id {
+        Some(rule) => format!("{}/{}", f.tool, rule),
+        None => f.tool.clone(),
+    }
+}
+
+/// `= note:` and `= help:` lines for what the tool knows beyond the location.
+fn notes(out: &mut String, f: &Finding, pad: &str, style: Style) {
+    let mut note = |kind: &str, text: &str| {
+        let _ = writeln!(out, "{pad} {} {kind}: {text}", style.gutter("="));
+    };
+    if f.baseline_state == Some(BaselineState::New) {
+        note("note", "new since the compared report");
+    }
+    if !f.cwe.is_empty() {
+        let cwe: Vec<String> = f.cwe.iter().map(|c| format!("CWE-{c}")).collect();
+        note("note", &cwe.join(", "));
+    }
+    if !f.correlated.is_empty() {
+        let others: Vec<String> = f
+            .correlated
+            .iter()
+            .map(|c| match &c.rule_id {
+                Some(rule) => format!("{}/{rule}", c.tool),
+                None => c.tool.clone(),
+            })
+            .collect();
+        note("note", &format!("also reported by {}", others.join(", ")));
+    }
+    for related in &f.related_locations {
+        let mut location = related.location.file.to_string_lossy().to_string();
+        if let Some(line) = related.location.line {
+            let _ = write!(location, ":{line}");
+        }
+        note(
+            "note",
+            &format!("{location}: {}", related.message.as_deref().unwrap_or("")),
+        );
+    }
+    if let Some(fix) = &f.fix {
+        note("help", &format!("replace with `{}`", fix.replacement));
+    }
+    if let Some(uri) = &f.help_uri {
+        note("help", uri);
+    }
+}
+
+/// Underline the range from 1-based `column` to `end`, or the token starting
+/// at `column`, keeping tabs so the carets line up with the source.
+fn caret(text: &str, column: usize, end: Option<u32>, severity: Severity, style: Style) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let start = (column - 1).min(chars.len());
+    let indent: String = chars[..start]
+        .iter()
+        .map(|c| if *c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let is_word = |c: &char| c.is_alphanumeric() || *c == '_';
+    let width = match (end, chars.get(start)) {
+        (Some(end), _) if end as usize >= column => end as usize - column + 1,
+        (_, Some(c)) if is_word(c) => chars[start..].iter().take_while(|c| is_word(c)).count(),
+        _ => 1,
+    };
+    let marks = "^".repeat(width);
+    format!("{indent}{}", style.paint(color(severity), &marks))
+}
+
+/// ANSI color of a severity, bold
+fn color(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "1;31",
+        Severity::Warning => "1;33",
+        Severity::Style => "1;35",
+        Severity::Info => "1;36",
+        Severity::Note => "1;32",
+    }
+}
@@ -1,77 +1,534 @@
-use crate::model::{Finding, Severity};
+use super::{emit, percent_encode, relative_to_project, OutputFormat, Report, SourceCache};
+use crate::fingerprint;
+use crate::model::{BaselineState, Finding, Fix, Kind, Location, RelatedLocation, Severity};
+use crate::runner::ToolRun;
+use crate::tools;
 use anyhow::Result;
-use std::collections::HashMap;
-use std::path::Path;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
-pub async fn write(findings: &[Finding], output_dir: &Path) -> Result<()> {
-    // Group findings by tool
-    let mut by_tool: HashMap<&str, Vec<&Finding>> = HashMap::new();
-    for f in findings {
+const SRCROOT: &str = "%SRCROOT%";
+
+pub async fn write(report: &Report<'_>) -> Result<()> {
+    // One SARIF run per tool, in a stable order. Tools that ran without
+    // findings still get a run so their invocation is recorded.
+    let mut by_tool: BTreeMap<&str, Vec<&Finding>> = BTreeMap::new();
+    for run in report.runs {
+        by_tool.entry(run.tool_name.as_str()).or_default();
+    }
+    // Fixed findings of a comparison are results with an `absent` state
+    for f in report.findings.iter().chain(report.fixed) {
         by_tool.entry(f.tool.as_str()).or_default().push(f);
     }
 
+    let root_uri = directory_uri(report.project_path);
+    let working_uri = report
+        .metadata
+        .working_directory
+        .as_deref()
+        .and_then(directory_uri);
+    // SARIF requires a repository URI to record the revision
+    let provenance = report.metadata.git.as_ref().and_then(|git| {
+        let mut vcs = json!({
+            "repositoryUri": git.remote.as_deref()?,
+            "revisionId": git.commit,
+            "mappedTo": { "uriBaseId": SRCROOT }
+        });
+        if let Some(branch) = &git.branch {
+            vcs["branch"] = branch.as_str().into();
+        }
+        Some(vcs)
+    });
     let mut runs = Vec::new();
     for (tool_name, tool_findings) in &by_tool {
-        let results: Vec<serde_json::Value> = tool_findings
-            .iter()
-            .map(|f| {
-                let level = match f.severity {
-                    Severity::Error => "error",
-                    Severity::Warning => "warning",
-                    Severity::Info | Severity::Style => "note",
-                    Severity::Note => "note",
-                };
-
-                let mut location = serde_json::json!({
-                    "physicalLocation": {
-                        "artifactLocation": {
-                            "uri": f.location.file.to_string_lossy()
-                        }
-                    }
-                });
-
-                if let Some(line) = f.location.line {
-                    location["physicalLocation"]["region"] =
-                        serde_json::json!({ "startLine": line });
-                    if let Some(col) = f.location.column {
-                        location["physicalLocation"]["region"]["startColumn"] = col.into();
-                    }
-                }
+        let tool_run = report.runs.iter().find(|r| r.tool_name == *tool_name);
+        let mut run = build_run(
+            tool_name,
+            tool_findings,
+            tool_run,
+            report.project_path,
+            root_uri.as_deref(),
+            working_uri.as_deref(),
+        );
+        if let Some(vcs) = &provenance {
+            run["versionControlProvenance"] = json!([vcs]);
+        }
+        runs.push(run);
+    }
 
-                let mut result = serde_json::json!({
-                    "level": level,
-                    "message": { "text": f.message },
-                    "locations": [location]
-                });
+    let sarif = json!({
+        "$schema": "https://raw.githubusercontent.com/oasis-tcs/sarif-spec/main/sarif-2.1/schema/sarif-schema-2.1.0.json",
+        "version": "2.1.0",
+        "runs": runs,
+        "properties": { "metadata": report.metadata }
+    });
 
-                if let Some(rule_id) = &f.rule_id {
-                    result["ruleId"] = serde_json::Value::String(rule_id.clone());
-                }
+    let json = serde_json::to_string_pretty(&sarif)?;
+    emit(report, OutputFormat::Sarif, "report.sarif", json).await
+}
 
-                result
-            })
-            .collect();
+fn build_run(
+    tool_name: &str,
+    findings: &[&Finding],
+    tool_run: Option<&ToolRun>,
+    project_path: &Path,
+    root_uri: Option<&str>,
+    working_uri: Option<&str>,
+) -> Value {
+    // Rules, indexed in id order; a rule's security severity is that of its
+    // worst finding (e.g. scan-build's unix.Malloc reports leaks and double frees)
+    let mut rule_findings: BTreeMap<&str, &Finding> = BTreeMap::new();
+    for f in findings {
+        if let Some(rule_id) = &f.rule_id {
+            let example = rule_findings.entry(rule_id.as_str()).or_insert(f);
+            if f.security_severity > example.security_severity {
+                *example = f;
+            }
+        }
+    }
+    let rule_index: BTreeMap<&str, usize> = rule_findings
+        .keys()
+        .enumerate()
+        .map(|(i, id)| (*id, i))
+        .collect();
+    let rules: Vec<Value> = rule_findings
+        .iter()
+        .map(|(id, f)| rule_descriptor(id, f))
+        .collect();
+
+    // Artifacts: every file referenced by a finding, relative to the project
+    // root when possible
+    let mut artifacts: BTreeMap<String, ArtifactRef> = BTreeMap::new();
+    let mut result_uris = Vec::new();
+    for f in findings {
+        let artifact = artifact_ref(&f.location.file, project_path);
+        let key = artifact.key();
+        result_uris.push(key.clone());
+        artifacts.entry(key).or_insert(artifact);
+    }
+    let artifact_index: BTreeMap<&str, usize> = artifacts
+        .keys()
+        .enumerate()
+        .map(|(i, k)| (k.as_str(), i))
+        .collect();
+    let columns = Columns::new(project_path);
+
+    let results: Vec<Value> = findings
+        .iter()
+        .zip(&result_uris)
+        .map(|(f, key)| {
+            let artifact = &artifacts[key];
+            let mut artifact_location = artifact.location();
+            artifact_location["index"] = artifact_index[key.as_str()].into();
 
-        runs.push(serde_json::json!({
-            "tool": {
-                "driver": {
-                    "name": tool_name,
-                    "informationUri": format!("https://github.com/search?q={}", tool_name)
+            let mut location = json!({
+                "physicalLocation": {
+                    "artifactLocation": artifact_location
+                }
+            });
+            if let Some(mut region) = columns.region(&f.location) {
+                if let Some(snippet) = &f.snippet {
+                    region["snippet"] = json!({ "text": snippet });
                 }
-            },
-            "results": results
-        }));
+                location["physicalLocation"]["region"] = region;
+            }
+
+            let mut result = json!({
+                "level": level(f.severity),
+                "message": { "text": f.message },
+                "locations": [location],
+                "partialFingerprints": { fingerprint::SARIF_KEY: f.fingerprint }
+            });
+
+            if let Some(rule_id) = &f.rule_id {
+                result["ruleId"] = Value::String(rule_id.clone());
+                result["ruleIndex"] = rule_index[rule_id.as_str()].into();
+            }
+            if let Some(state) = f.baseline_state {
+                result["baselineState"] = baseline_state(state).into();
+            }
+            if !f.related_locations.is_empty() {
+                let related: Vec<Value> = f
+                    .related_locations
+                    .iter()
+                    .enumerate()
+                    .map(|(id, r)| {
+                        let mut related = related_location(r, project_path, &columns);
+                        related["id"] = id.into();
+                        related
+                    })
+                    .collect();
+                result["relatedLocations"] = related.into();
+            }
+            if !f.code_flow.is_empty() {
+                let steps: Vec<Value> = f
+                    .code_flow
+                    .iter()
+                    .map(|step| {
+                        json!({ "location": related_location(step, project_path, &columns) })
+                    })
+                    .collect();
+                result["codeFlows"] = json!([{ "threadFlows": [{ "locations": steps }] }]);
+            }
+            if let Some(fix) = f
+                .fix
+                .as_ref()
+                .and_then(|fix| sarif_fix(f, fix, project_path, &columns))
+            {
+                result["fixes"] = json!([fix]);
+            }
+            let properties = result_properties(f);
+            if !properties.is_empty() {
+                result["properties"] = properties.into();
+            }
+
+            result
+        })
+        .collect();
+
+    let mut driver = json!({
+        "name": tool_name,
+        "rules": rules
+    });
+    if let Some(uri) = tools::information_uri(tool_name) {
+        driver["informationUri"] = uri.into();
+    }
+    if let Some(version) = tool_run.and_then(|r| r.version.as_deref()) {
+        driver["version"] = version.into();
     }
 
-    let sarif = serde_json::json!({
-        "$schema": "https://raw.githubusercontent.com/oasis-tcs/sarif-spec/main/sarif-2.1/schema/sarif-schema-2.1.0.json",
-        "version": "2.1.0",
-        "runs": runs
+    let mut run = json!({
+        "tool": { "driver": driver },
+        "artifacts": artifacts.values().map(ArtifactRef::describe).collect::<Vec<_>>(),
+        "results": results,
+        "columnKind": "utf16CodeUnits"
     });
+    if let Some(uri) = root_uri {
+        run["originalUriBaseIds"] = json!({ SRCROOT: { "uri": uri } });
+    }
+    if let Some(tool_run) = tool_run {
+        run["invocations"] = json!([invocation(tool_run, working_uri)]);
+    }
+    run
+}
 
-    let path = output_dir.join("report.sarif");
-    let json = serde_json::to_string_pretty(&sarif)?;
-    tokio::fs::write(&path, json).await?;
-    eprintln!("[sast] SARIF report saved to {}", path.display());
-    Ok(())
+fn baseline_state(state: BaselineState) -> &'static str {
+    match state {
+        BaselineState::New => "new",
+        BaselineState::Unchanged => "unchanged",
+        BaselineState::Fixed => "absent",
+    }
+}
+
+/// Tools report columns in bytes, while runs declare `utf16CodeUnits` (what
+/// GitHub code scanning expects); the two differ on lines with non-ASCII
+/// text. Columns are converted using the source lines.
+struct Columns<'a> {
+    sources: RefCell<SourceCache<'a>>,
+}
+
+impl<'a> Columns<'a> {
+    fn new(project_path: &'a Path) -> Self {
+        Self {
+            sources: RefCell::new(SourceCache::new(project_path)),
+        }
+    }
+
+    /// Region of a location, or `None` when the tool gave no line.
+    fn region(&self, location: &Location) -> Option<Value> {
+        let line = location.line.filter(|l| *l > 0)?;
+        let mut region = json!({ "startLine": line });
+        if let Some(col) = location.column.filter(|c| *c > 0) {
+            region["startColumn"] = self.utf16(&location.file, line, col).into();
+        }
+        let end_line = location.end_line.filter(|l| *l >= line);
+        if let Some(end_line) = end_line {
+            region["endLine"] = end_line.into();
+        }
+        // SARIF end columns are exclusive
+        if let Some(end_col) = location.end_column.filter(|c| *c > 0) {
+            let end_line = end_line.unwrap_or(line);
+            region["endColumn"] = self.utf16(&location.file, end_line, end_col + 1).into();
+        }
+        Some(region)
+    }
+
+    /// The 1-based UTF-16 column of the 1-based byte `column` on `line`;
+    /// unchanged when the file can't be read.
+    fn utf16(&self, file: &Path, line: u32, column: u32) -> u32 {
+        let mut sources = self.sources.borrow_mut();
+        let Some(text) = sources
+            .lines(file)
+            .and_then(|l| l.get(line as usize - 1))
+            .map(|l| l.as_bytes())
+        else {
+            return column;
+        };
+        let bytes = column as usize - 1;
+        let prefix = &text[..bytes.min(text.len())];
+        let units = String::from_utf8_lossy(prefix).encode_utf16().count();
+        // Columns past the end of the line (e.g. the newline) stay bytes
+        (units + bytes.saturating_sub(text.len()) + 1) as u32
+    }
+}
+
+fn related_location(related: &RelatedLocation, project_path: &Path, columns: &Columns) -> Value {
+    let mut physical = json!({
+        "artifactLocation": artifact_ref(&related.location.file, project_path).location()
+    });
+    if let Some(region) = columns.region(&related.location) {
+        physical["region"] = region;
+    }
+    let mut location = json!({ "physicalLocation": physical });
+    if let Some(message) = &related.message {
+        location["message"] = json!({ "text": message });
+    }
+    location
+}
+
+/// The fix replaces its own region, else the finding's; without an end it
+/// inserts. `None` when there's no line and column to put the change at.
+fn sarif_fix(f: &Finding, fix: &Fix, project_path: &Path, columns: &Columns) -> Option<Value> {
+    let location = fix.location.as_ref().unwrap_or(&f.location);
+    location.column.filter(|c| *c > 0)?;
+    let mut deleted = columns.region(location)?;
+    if location.end_column.is_none() {
+        deleted["endLine"] = deleted["startLine"].clone();
+        deleted["endColumn"] = deleted["startColumn"].clone();
+    }
+    let mut sarif_fix = json!({
+        "artifactChanges": [{
+            "artifactLocation": artifact_ref(&location.file, project_path).location(),
+            "replacements": [{
+                "deletedRegion": deleted,
+                "insertedContent": { "text": fix.replacement }
+            }]
+        }]
+    });
+    if let Some(description) = &fix.description {
+        sarif_fix["description"] = json!({ "text": description });
+    }
+    Some(sarif_fix)
+}
+
+/// Tags for CWE and OWASP in the form GitHub code scanning reads, plus the
+/// kind, security severity, confidence, correlated tools and whatever else
+/// the tool reported.
+fn result_properties(f: &Finding) -> serde_json::Map<String, Value> {
+    let mut properties: serde_json::Map<String, Value> = f
+        .properties
+        .iter()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    let tags = tags(f);
+    if !tags.is_empty() {
+        properties.insert("tags".into(), tags.into());
+    }
+    if let Some(kind) = f.kind {
+        properties.insert("kind".into(), kind.name().into());
+    }
+    if let Some(security_severity) = f.security_severity {
+        properties.insert("security-severity".into(), security_severity.score().into());
+    }
+    if let Some(confidence) = f.confidence {
+        properties.insert("confidence".into(), confidence.name().into());
+    }
+    if !f.correlated.is_empty() {
+        properties.insert("alsoReportedBy".into(), reported_by(f).into());
+    }
+    properties
+}
+
+/// `tool/rule` of every finding merged into this one, e.g.
+/// `scan-build/cplusplus.NewDelete`.
+fn reported_by(f: &Finding) -> Vec<String> {
+    f.correlated
+        .iter()
+        .map(|c| match &c.rule_id {
+            Some(rule) => format!("{}/{rule}", c.tool),
+            None => c.tool.clone(),
+        })
+        .collect()
+}
+
+fn tags(f: &Finding) -> Vec<String> {
+    let mut tags = Vec::new();
+    if f.kind == Some(Kind::Vulnerability) || !f.owasp.is_empty() {
+        tags.push("security".to_string());
+    }
+    tags.extend(f.cwe.iter().map(|c| format!("external/cwe/cwe-{c}")));
+    tags.extend(f.owasp.iter().map(|o| format!("external/owasp/{o}")));
+    tags
+}
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info | Severity::Style => "note",
+        Severity::Note => "note",
+    }
+}
+
+fn rule_descriptor(rule_id: &str, example: &Finding) -> Value {
+    let full = example.message.trim();
+    let short = full
+        .split_once(". ")
+        .map(|(first, _)| format!("{first}."))
+        .unwrap_or_else(|| full.to_string());
+
+    let mut rule = json!({
+        "id": rule_id,
+        "name": rule_id,
+        "shortDescription": { "text": short },
+        "fullDescription": { "text": full },
+        "defaultConfiguration": { "level": level(example.severity) },
+        "help": { "text": full }
+    });
+    let tags = tags(example);
+    if !tags.is_empty() {
+        rule["properties"] = json!({ "tags": tags });
+    }
+    // GitHub code scanning ranks security alerts by this score
+    if let Some(security_severity) = example.security_severity {
+        rule["properties"]["security-severity"] = security_severity.score().into();
+    }
+    if let Some(uri) = &example.help_uri {
+        rule["helpUri"] = Value::String(uri.clone());
+        rule["help"] = json!({
+            "text": format!("{full}\n\nSee {uri}"),
+            "markdown": format!("{full}\n\n[{rule_id}]({uri})")
+        });
+    }
+    rule
+}
+
+/// `working_uri` is where the scan ran, which a replay's directory is not.
+fn invocation(run: &ToolRun, working_uri: Option<&str>) -> Value {
+    let end = run.started_at + chrono::Duration::from_std(run.duration).unwrap_or_default();
+    let mut invocation = json!({
+        "commandLine": command_line(&run.argv),
+        "arguments": run.argv.iter().skip(1).collect::<Vec<_>>(),
+        "exitCode": run.exit_code,
+        // Tools commonly exit non-zero to signal findings; the run itself
+        // completed and its output was parsed
+        "executionSuccessful": true,
+        "startTimeUtc": run.started_at.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        "endTimeUtc": end.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+    });
+    if let Some(uri) = working_uri {
+        invocation["workingDirectory"] = json!({ "uri": uri });
+    }
+    invocation
+}
+
+fn command_line(argv: &[String]) -> String {
+    argv.iter()
+        .map(|a| {
+            if a.is_empty() || a.contains(|c: char| c.is_whitespace() || c == '"') {
+                format!("\"{}\"", a.replace('"', "\\\""))
+            } else {
+                a.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A file referenced by results: relative to `%SRCROOT%` when it lies in
+/// the project, otherwise an absolute URI.
+struct ArtifactRef {
+    uri: String,
+    relative: bool,
+    path: Option<PathBuf>,
+}
+
+impl ArtifactRef {
+    fn key(&self) -> String {
+        if self.relative {
+            format!("{SRCROOT}/{}", self.uri)
+        } else {
+            self.uri.clone()
+        }
+    }
+
+    fn location(&self) -> Value {
+        if self.relative {
+            json!({ "uri": self.uri, "uriBaseId": SRCROOT })
+        } else {
+            json!({ "uri": self.uri })
+        }
+    }
+
+    fn describe(&self) -> Value {
+        let mut artifact = json!({ "location": self.location() });
+        if let Some(data) = self.path.as_ref().and_then(|p| std::fs::read(p).ok()) {
+            artifact["length"] = data.len().into();
+            artifact["hashes"] = json!({ "sha-256": format!("{:x}", Sha256::digest(&data)) });
+        }
+        artifact
+    }
+}
+
+fn artifact_ref(file: &Path, project_path: &Path) -> ArtifactRef {
+    if let Some(rel) = relative_to_project(file, project_path) {
+        return ArtifactRef {
+            uri: path_to_uri(&rel),
+            relative: true,
+            path: Some(project_path.join(rel)),
+        };
+    }
+
+    match std::fs::canonicalize(file) {
+        Ok(absolute) => ArtifactRef {
+            uri: file_uri(&absolute),
+            relative: false,
+            path: Some(absolute),
+        },
+        // Not a file on disk (e.g. cppcheck's "nofile"); keep it as printed
+        Err(_) => ArtifactRef {
+            uri: path_to_uri(file),
+            relative: false,
+            path: None,
+        },
+    }
+}
+
+fn path_to_uri(path: &Path) -> String {
+    path.to_string_lossy()
+        .replace('\\', "/")
+        .split('/')
+        .map(percent_encode)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn file_uri(absolute: &Path) -> String {
+    // canonicalize() yields verbatim paths (\\?\C:\...) on Windows
+    let raw = absolute.to_string_lossy();
+    let raw = raw.strip_prefix(r"\\?\").unwrap_or(&raw);
+    let path = path_to_uri(Path::new(raw));
+    // Windows paths (C:/...) need a leading slash after file://
+    if path.starts_with('/') {
+        format!("file://{path}")
+    } else {
+        format!("file:///{path}")
+    }
+}
+
+/// Recorded directories may not exist where a report is replayed; an
+/// absolute one is then used as it is.
+fn directory_uri(dir: &Path) -> Option<String> {
+    let absolute = std::fs::canonicalize(dir)
+        .ok()
+        .or_else(|| dir.is_absolute().then(|| dir.to_path_buf()))?;
+    let uri = file_uri(&absolute);
+    Some(if uri.ends_with('/') {
+        uri
+    } else {
+        format!("{uri}/")
+    })
 }
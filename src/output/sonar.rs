@@ -0,0 +1,109 @@
+use super::{display_path, emit, OutputFormat, Report};
+use crate::model::{Finding, Kind, Location, Severity};
+use anyhow::{ensure, Result};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+const SEVERITIES: &[&str] = &["BLOCKER", "CRITICAL", "MAJOR", "MINOR", "INFO"];
+const TYPES: &[&str] = &["BUG", "VULNERABILITY", "CODE_SMELL"];
+
+/// Sonar's generic external issue format, imported with
+/// `sonar.externalIssuesReportPaths`.
+pub async fn write(report: &Report<'_>) -> Result<()> {
+    let options = &report.options.sonar;
+    check_values(&options.severity_map, SEVERITIES, "severity")?;
+    check_values(&options.type_map, TYPES, "type")?;
+
+    let issues: Vec<Value> = report
+        .findings
+        .iter()
+        .map(|f| {
+            let severity = options
+                .severity_map
+                .get(&f.severity)
+                .map(String::as_str)
+                .unwrap_or_else(|| default_severity(f.severity));
+            let issue_type = options
+                .type_map
+                .get(&f.severity)
+                .map(String::as_str)
+                .unwrap_or_else(|| default_type(f));
+
+            let mut issue = json!({
+                "engineId": f.tool,
+                "ruleId": f.rule_id.clone().unwrap_or_else(|| f.tool.clone()),
+                "severity": severity,
+                "type": issue_type,
+                "primaryLocation": location(&f.location, &f.message, report)
+            });
+            let secondary: Vec<Value> = f
+                .related_locations
+                .iter()
+                .chain(&f.code_flow)
+                .map(|r| location(&r.location, r.message.as_deref().unwrap_or(""), report))
+                .collect();
+            if !secondary.is_empty() {
+                issue["secondaryLocations"] = secondary.into();
+            }
+            issue
+        })
+        .collect();
+
+    let json = serde_json::to_string_pretty(&json!({ "issues": issues }))?;
+    emit(report, OutputFormat::Sonar, "sonar-issues.json", json).await
+}
+
+fn location(location: &Location, message: &str, report: &Report<'_>) -> Value {
+    let mut value = json!({
+        "message": message,
+        "filePath": display_path(&location.file, report.project_path)
+    });
+    if let Some(line) = location.line.filter(|l| *l > 0) {
+        value["textRange"] = json!({ "startLine": line });
+        // Sonar columns are 0-based and the end is exclusive
+        if let Some(column) = location.column.filter(|c| *c > 0) {
+            value["textRange"]["startColumn"] = (column - 1).into();
+        }
+        if let Some(end_line) = location.end_line.filter(|l| *l >= line) {
+            value["textRange"]["endLine"] = end_line.into();
+            if let Some(end_column) = location.end_column {
+                value["textRange"]["endColumn"] = end_column.into();
+            }
+        }
+    }
+    value
+}
+
+fn check_values(map: &HashMap<Severity, String>, allowed: &[&str], what: &str) -> Result<()> {
+    for value in map.values() {
+        ensure!(
+            allowed.contains(&value.as_str()),
+            "Invalid sonar {} '{}'. Use: {}",
+            what,
+            value,
+            allowed.join(", ")
+        );
+    }
+    Ok(())
+}
+
+fn default_severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "CRITICAL",
+        Severity::Warning => "MAJOR",
+        Severity::Style => "MINOR",
+        Severity::Info | Severity::Note => "INFO",
+    }
+}
+
+fn default_type(f: &Finding) -> &'static str {
+    match f.kind {
+        Some(Kind::Vulnerability) => "VULNERABILITY",
+        Some(Kind::Bug) => "BUG",
+        Some(Kind::CodeSmell) => "CODE_SMELL",
+        None => match f.severity {
+            Severity::Error | Severity::Warning => "BUG",
+            Severity::Style | Severity::Info | Severity::Note => "CODE_SMELL",
+        },
+    }
+}
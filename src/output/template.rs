@@ -0,0 +1,137 @@
+use super::{emit, flat_field, report_path, OutputFormat, Report};
+use crate::model::{Finding, Severity, FLAT_FIELDS, FLAT_SCHEMA_VERSION};
+use anyhow::{ensure, Context, Result};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Extensions marking a file as a template rather than the report itself
+const TEMPLATE_EXTENSIONS: &[&str] = &[".j2", ".jinja2", ".jinja"];
+
+/// Render the user template from `report.template.path` over the context
+/// documented in the README.
+pub async fn write(report: &Report<'_>) -> Result<()> {
+    let options = &report.options.template;
+    let template_path = options
+        .path
+        .as_deref()
+        .context("The template format needs `report.template.path` in the config")?;
+    let name = template_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .with_context(|| format!("Invalid template path '{}'", template_path.display()))?;
+
+    // Templates may include or extend files next to them
+    let mut env = minijinja::Environment::new();
+    env.set_loader(minijinja::path_loader(
+        template_path.parent().unwrap_or(Path::new(".")),
+    ));
+    let template = env
+        .get_template(name)
+        .with_context(|| format!("Failed to load template '{}'", template_path.display()))?;
+    let rendered = template
+        .render(context(report))
+        .with_context(|| format!("Failed to render template '{}'", template_path.display()))?;
+
+    let file_name = options.output.clone().unwrap_or_else(|| {
+        TEMPLATE_EXTENSIONS
+            .iter()
+            .find_map(|ext| name.strip_suffix(ext))
+            .unwrap_or(name)
+            .to_string()
+    });
+    let path = report_path(report, OutputFormat::Template, &file_name);
+    ensure!(
+        report.to_stdout
+            || std::fs::canonicalize(&path).ok() != std::fs::canonicalize(template_path).ok(),
+        "Template report would overwrite its template '{}'; set `report.template.output`",
+        template_path.display()
+    );
+    emit(report, OutputFormat::Template, &file_name, rendered).await
+}
+
+/// Variables available to templates: `metadata`, `summary`, `runs`,
+/// `findings` and, after a comparison, the `fixed` findings.
+fn context(report: &Report<'_>) -> Value {
+    let flat = |f: &Finding| {
+        let mut fields = Map::new();
+        for field in FLAT_FIELDS {
+            let value = flat_field(f, field, report.project_path).unwrap_or_default();
+            fields.insert(field.to_string(), value);
+        }
+        fields.insert("snippet".into(), f.snippet.clone().into());
+        fields.insert("owasp".into(), f.owasp.clone().into());
+        for (name, value) in [
+            (
+                "related_locations",
+                serde_json::to_value(&f.related_locations),
+            ),
+            ("code_flow", serde_json::to_value(&f.code_flow)),
+            ("fix", serde_json::to_value(&f.fix)),
+            ("properties", serde_json::to_value(&f.properties)),
+        ] {
+            fields.insert(name.into(), value.unwrap_or_default());
+        }
+        Value::Object(fields)
+    };
+    let findings: Vec<Value> = report.findings.iter().map(flat).collect();
+    let fixed: Vec<Value> = report.fixed.iter().map(flat).collect();
+
+    let mut runs: Vec<Value> = report
+        .runs
+        .iter()
+        .map(|run| {
+            json!({
+                "tool": run.tool_name,
+                "status": "success",
+                "exit_code": run.exit_code,
+                "version": run.version,
+                "started_at": run.started_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                "duration_secs": run.duration.as_secs_f64(),
+                "findings": report.findings.iter().filter(|f| f.tool == run.tool_name).count()
+            })
+        })
+        .collect();
+    runs.extend(report.failed_tools.iter().map(|failed| {
+        json!({
+            "tool": failed.name,
+            "status": "failed",
+            "error": failed.error,
+            "findings": 0
+        })
+    }));
+
+    let mut by_severity = Map::new();
+    for severity in Severity::ALL {
+        let count = report
+            .findings
+            .iter()
+            .filter(|f| f.severity == severity)
+            .count();
+        by_severity.insert(severity.name().to_string(), count.into());
+    }
+    let mut by_tool: BTreeMap<&str, usize> = BTreeMap::new();
+    for f in report.findings {
+        *by_tool.entry(f.tool.as_str()).or_default() += 1;
+    }
+
+    let mut metadata = serde_json::to_value(report.metadata).unwrap_or_default();
+    metadata["schema_version"] = FLAT_SCHEMA_VERSION.into();
+    metadata["project_name"] = report.project_name.into();
+    metadata["generated_at"] = chrono::Utc::now()
+        .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        .into();
+
+    json!({
+        "metadata": metadata,
+        "summary": {
+            "total": report.findings.len(),
+            "by_severity": by_severity,
+            "by_tool": by_tool,
+            "failed_tools": report.failed_tools.len()
+        },
+        "runs": runs,
+        "findings": findings,
+        "fixed": fixed
+    })
+}
@@ -0,0 +1,166 @@
+//! Path normalization: every tool prints paths its own way (as passed on the
+//! command line, absolute, relative to the working directory, or as seen
+//! inside a build container). Findings are rewritten to paths relative to
+//! the project root so the same file looks the same in every report.
+
+use crate::model::Finding;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Component, Path, PathBuf};
+
+pub struct Normalizer {
+    /// Project root as given, made absolute
+    root: PathBuf,
+    /// Project root with symlinks resolved, when it exists
+    canonical_root: Option<PathBuf>,
+    cwd: PathBuf,
+    /// `(from, to)` prefixes, longest `from` first
+    remap: Vec<(PathBuf, PathBuf)>,
+    resolved: HashMap<PathBuf, Resolved>,
+}
+
+#[derive(Clone)]
+struct Resolved {
+    path: PathBuf,
+    inside: bool,
+}
+
+impl Normalizer {
+    pub fn new(project_path: &Path, remap: &HashMap<String, String>) -> Self {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let root = clean(&cwd.join(project_path));
+        let mut remap: Vec<(PathBuf, PathBuf)> = remap
+            .iter()
+            .map(|(from, to)| (clean(Path::new(from)), root.join(to)))
+            .collect();
+        remap.sort_by_key(|(from, _)| std::cmp::Reverse(from.components().count()));
+        Self {
+            canonical_root: std::fs::canonicalize(&root).ok(),
+            root,
+            cwd,
+            remap,
+            resolved: HashMap::new(),
+        }
+    }
+
+    /// Rewrite the locations of `findings` relative to the project root.
+    /// Returns the files that lie outside the project; they keep an
+    /// absolute path.
+    pub fn normalize(&mut self, findings: &mut [Finding]) -> BTreeSet<PathBuf> {
+        let mut outside = BTreeSet::new();
+        for f in findings.iter_mut() {
+            let locations = std::iter::once(&mut f.location).chain(
+                f.related_locations
+                    .iter_mut()
+                    .chain(f.code_flow.iter_mut())
+                    .map(|r| &mut r.location),
+            );
+            for location in locations {
+                if location.file.as_os_str().is_empty() {
+                    continue;
+                }
+                let resolved = self.resolve(&location.file);
+                if !resolved.inside {
+                    outside.insert(resolved.path.clone());
+                }
+                location.file = resolved.path;
+            }
+        }
+        outside
+    }
+
+    fn resolve(&mut self, file: &Path) -> Resolved {
+        if let Some(resolved) = self.resolved.get(file) {
+            return resolved.clone();
+        }
+        let resolved = self.locate(&self.remapped(file));
+        self.resolved.insert(file.to_path_buf(), resolved.clone());
+        resolved
+    }
+
+    fn remapped(&self, file: &Path) -> PathBuf {
+        let file = clean(file);
+        for (from, to) in &self.remap {
+            if let Ok(rest) = file.strip_prefix(from) {
+                return to.join(rest);
+            }
+        }
+        file
+    }
+
+    /// Relative paths are tried against the working directory, which is
+    /// where the tools ran, then against the project root.
+    fn locate(&self, file: &Path) -> Resolved {
+        let candidates = if file.is_absolute() {
+            vec![file.to_path_buf()]
+        } else {
+            vec![self.cwd.join(file), self.root.join(file)]
+        };
+        let mut on_disk = None;
+        for candidate in &candidates {
+            if let Ok(absolute) = std::fs::canonicalize(candidate) {
+                if let Some(rel) = self.strip_root(&absolute) {
+                    return Resolved {
+                        path: rel,
+                        inside: true,
+                    };
+                }
+                on_disk.get_or_insert(absolute);
+            }
+        }
+        if let Some(absolute) = on_disk {
+            return Resolved {
+                path: absolute,
+                inside: false,
+            };
+        }
+
+        // Not on disk (e.g. replayed elsewhere): decide by the path alone
+        let absolute = clean(&candidates[0]);
+        if let Some(rel) = self.strip_root(&absolute) {
+            return Resolved {
+                path: rel,
+                inside: true,
+            };
+        }
+        match candidates.get(1) {
+            Some(in_project) if clean(in_project).starts_with(&self.root) => Resolved {
+                path: clean(file),
+                inside: true,
+            },
+            _ => Resolved {
+                path: absolute,
+                inside: false,
+            },
+        }
+    }
+
+    fn strip_root(&self, absolute: &Path) -> Option<PathBuf> {
+        std::iter::once(&self.root)
+            .chain(&self.canonical_root)
+            .find_map(|root| absolute.strip_prefix(root).ok())
+            // The project itself, e.g. for tools without per-file output
+            .map(|rel| match rel.as_os_str().is_empty() {
+                true => PathBuf::from("."),
+                false => rel.to_path_buf(),
+            })
+    }
+}
+
+/// Resolve `.` and `..` without touching the filesystem.
+fn clean(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if matches!(out.components().next_back(), Some(Component::Normal(_))) {
+                    out.pop();
+                } else if !out.has_root() {
+                    out.push("..");
+                }
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
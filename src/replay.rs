@@ -0,0 +1,125 @@
+use crate::metadata::ScanMetadata;
+use crate::runner::ToolRun;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Index of the raw tool outputs recorded by a scan (`raw/manifest.json`).
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct Manifest {
+    pub sast_version: String,
+    pub project_path: PathBuf,
+    pub runs: Vec<RunRecord>,
+    /// Metadata of the recorded scan
+    #[serde(default)]
+    pub metadata: Option<ScanMetadata>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RunRecord {
+    pub tool: String,
+    pub argv: Vec<String>,
+    pub exit_code: i32,
+    pub version: Option<String>,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub duration_secs: f64,
+    /// File holding the captured stdout, relative to the manifest
+    pub stdout: PathBuf,
+    /// File holding the captured stderr, relative to the manifest
+    pub stderr: PathBuf,
+}
+
+/// Save raw stdout/stderr of every run plus a manifest into `dir`.
+pub async fn save(runs: &[ToolRun], dir: &Path, metadata: &ScanMetadata) -> Result<()> {
+    write(runs, dir, &metadata.project_path, Some(metadata.clone())).await?;
+    eprintln!("[sast] Raw tool outputs saved to {}/", dir.display());
+    Ok(())
+}
+
+/// Write `runs` in the replay layout; the result cache stores its entries
+/// the same way.
+pub async fn write(
+    runs: &[ToolRun],
+    dir: &Path,
+    project_path: &Path,
+    metadata: Option<ScanMetadata>,
+) -> Result<()> {
+    tokio::fs::create_dir_all(dir).await?;
+
+    let mut records = Vec::new();
+    for run in runs {
+        let stdout = PathBuf::from(format!("{}.stdout", run.tool_name));
+        let stderr = PathBuf::from(format!("{}.stderr", run.tool_name));
+        tokio::fs::write(dir.join(&stdout), &run.stdout).await?;
+        tokio::fs::write(dir.join(&stderr), &run.stderr).await?;
+
+        records.push(RunRecord {
+            tool: run.tool_name.clone(),
+            argv: run.argv.clone(),
+            exit_code: run.exit_code,
+            version: run.version.clone(),
+            started_at: run.started_at,
+            duration_secs: run.duration.as_secs_f64(),
+            stdout,
+            stderr,
+        });
+    }
+
+    let manifest = Manifest {
+        sast_version: env!("CARGO_PKG_VERSION").to_string(),
+        project_path: project_path.to_path_buf(),
+        runs: records,
+        metadata,
+    };
+
+    let path = dir.join(MANIFEST_FILE);
+    tokio::fs::write(&path, serde_json::to_string_pretty(&manifest)?).await?;
+    Ok(())
+}
+
+/// The directory holding the manifest: `dir` itself, or `dir/raw` for a
+/// report directory.
+pub fn raw_dir(dir: &Path) -> PathBuf {
+    if dir.join(MANIFEST_FILE).exists() {
+        dir.to_path_buf()
+    } else {
+        dir.join("raw")
+    }
+}
+
+/// Load a manifest and its raw outputs back into `ToolRun`s.
+pub async fn load(dir: &Path) -> Result<(Manifest, Vec<ToolRun>)> {
+    let dir = raw_dir(dir);
+    let manifest_path = dir.join(MANIFEST_FILE);
+    let text = tokio::fs::read_to_string(&manifest_path)
+        .await
+        .with_context(|| format!("No replay manifest at '{}'", manifest_path.display()))?;
+    let manifest: Manifest = serde_json::from_str(&text)
+        .with_context(|| format!("Invalid manifest '{}'", manifest_path.display()))?;
+
+    let mut runs = Vec::new();
+    for record in &manifest.runs {
+        let read = |file: &Path| {
+            let path = dir.join(file);
+            async move {
+                tokio::fs::read(&path)
+                    .await
+                    .with_context(|| format!("Missing raw output '{}'", path.display()))
+            }
+        };
+
+        runs.push(ToolRun {
+            tool_name: record.tool.clone(),
+            exit_code: record.exit_code,
+            stdout: read(&record.stdout).await?,
+            stderr: read(&record.stderr).await?,
+            argv: record.argv.clone(),
+            version: record.version.clone(),
+            started_at: record.started_at,
+            duration: std::time::Duration::from_secs_f64(record.duration_secs.max(0.0)),
+        });
+    }
+
+    Ok((manifest, runs))
+}
@@ -1,15 +1,116 @@
+use crate::cache::{Cache, ToolKey};
+use crate::changes::ChangeSet;
 use crate::config::{OutputStream, ToolConfig};
+use crate::detect;
+use crate::filter::PathFilter;
 use anyhow::{Context, Result};
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub struct ToolRun {
     pub tool_name: String,
-    #[allow(dead_code)]
     pub exit_code: i32,
     pub stdout: Vec<u8>,
     pub stderr: Vec<u8>,
+    /// Full command line, executable first
+    pub argv: Vec<String>,
+    /// First line of `<command> --version`, when the tool reports one
+    pub version: Option<String>,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub duration: std::time::Duration,
+}
+
+/// A tool that could not be executed.
+#[derive(Debug)]
+pub struct FailedTool {
+    pub name: String,
+    pub error: String,
+}
+
+/// A configured tool left out by automatic selection.
+#[derive(Debug)]
+pub struct SkippedTool {
+    pub name: String,
+    pub reason: String,
+}
+
+/// Pick the configured tools that apply to the project: their declared
+/// languages or file globs must be present and their command installed.
+pub fn select_tools(
+    tool_configs: &HashMap<String, ToolConfig>,
+    project_path: &Path,
+) -> Result<(Vec<String>, Vec<SkippedTool>)> {
+    let mut skipped_dirs = Vec::new();
+    let files: Vec<String> = if project_path.is_dir() {
+        detect::walk_files(project_path, &mut skipped_dirs)?
+            .iter()
+            .map(|f| {
+                f.strip_prefix(project_path)
+                    .unwrap_or(f)
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect()
+    } else {
+        vec![project_path.to_string_lossy().to_string()]
+    };
+    let languages: Vec<&str> = files
+        .iter()
+        .filter_map(|f| Path::new(f).extension())
+        .filter_map(|e| detect::Language::from_extension(&e.to_string_lossy()))
+        .map(|l| l.name())
+        .collect();
+
+    let mut names: Vec<&String> = tool_configs.keys().collect();
+    names.sort();
+
+    let mut selected = Vec::new();
+    let mut skipped = Vec::new();
+    for name in names {
+        let config = &tool_configs[name];
+
+        // Either a listed language or a matching file makes the tool apply
+        let has_language = config
+            .languages
+            .iter()
+            .any(|l| languages.contains(&l.to_lowercase().as_str()));
+        let has_file = matches_any(&config.files, &files);
+        let mut missing = Vec::new();
+        if !config.languages.is_empty() {
+            missing.push(format!("no {} sources", config.languages.join("/")));
+        }
+        if !config.files.is_empty() {
+            missing.push(format!("no files matching {}", config.files.join(", ")));
+        }
+
+        let reason = if !missing.is_empty() && !has_language && !has_file {
+            Some(missing.join(" and "))
+        } else if detect::find_executable(&config.command).is_none() {
+            Some(format!("'{}' is not installed", config.command))
+        } else {
+            None
+        };
+
+        match reason {
+            Some(reason) => skipped.push(SkippedTool {
+                name: name.clone(),
+                reason,
+            }),
+            None => selected.push(name.clone()),
+        }
+    }
+
+    Ok((selected, skipped))
+}
+
+fn matches_any(globs: &[String], files: &[String]) -> bool {
+    let patterns: Vec<glob::Pattern> = globs
+        .iter()
+        .filter_map(|g| glob::Pattern::new(g).ok())
+        .collect();
+    files.iter().any(|f| patterns.iter().any(|p| p.matches(f)))
 }
 
 fn interpolate_args(args: &[String], project_path: &Path, output_dir: &Path) -> Vec<String> {
@@ -21,12 +122,208 @@ fn interpolate_args(args: &[String], project_path: &Path, output_dir: &Path) ->
         .collect()
 }
 
+/// Files matching the tool's `append_sources` glob that the path filters
+/// keep, limited to `changed` ones for incremental scans; `None` when the tool takes no file list.
+pub fn source_files(
+    config: &ToolConfig,
+    project_path: &Path,
+    paths: &PathFilter,
+    changed: Option<&ChangeSet>,
+) -> Option<Vec<String>> {
+    let pattern = config.append_sources.as_ref()?;
+    let full_pattern = format!("{}/{}", project_path.display(), pattern);
+    let files = glob::glob(&full_pattern)
+        .unwrap_or_else(|_| glob::glob("").unwrap())
+        .flatten()
+        .filter(|path| {
+            let Ok(rel) = path.strip_prefix(project_path) else {
+                return changed.is_none();
+            };
+            paths.keeps(&rel.to_string_lossy().replace('\\', "/"))
+                && changed.is_none_or(|changed| changed.contains(rel))
+        })
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    Some(files)
+}
+
+/// Per-scan settings shared by every tool run.
+#[derive(Clone, Default)]
+pub struct RunOptions {
+    /// Incremental scans give tools that take a file list only changed files
+    pub changed: Option<Arc<ChangeSet>>,
+    /// The `include_paths`/`exclude_paths` filters, applied to file lists too
+    pub paths: Arc<PathFilter>,
+    /// Where results are reused from and stored; `None` with `--no-cache`
+    pub cache: Option<Arc<Cache>>,
+}
+
 async fn run_single_tool(
     name: &str,
     config: &ToolConfig,
     project_path: &Path,
     output_dir: &Path,
+    options: &RunOptions,
 ) -> Result<ToolRun> {
+    let sources = source_files(
+        config,
+        project_path,
+        &options.paths,
+        options.changed.as_deref(),
+    )
+    .unwrap_or_default();
+
+    // On Windows, use scan-build.bat for scan-build
+    let command_name = if cfg!(windows) && config.command == "scan-build" {
+        "scan-build.bat".to_string()
+    } else {
+        config.command.clone()
+    };
+    let version = tool_version(&command_name).await;
+
+    // Results are only known to match for a known tool version. Tools that
+    // write files (scan-build's HTML pages) always run: only streams are kept
+    let cached = options
+        .cache
+        .as_deref()
+        .filter(|_| cacheable(config))
+        .zip(version.as_deref())
+        .and_then(|(cache, version)| {
+            ToolKey::new(name, config, version, project_path).map(|key| (cache, key))
+        });
+    let Some((cache, key)) = cached else {
+        eprintln!("[sast] Running {}...", name);
+        let args = command_args(config, project_path, output_dir, sources);
+        return execute(name, &command_name, args, version).await;
+    };
+
+    let relative = |file: &String| {
+        let path = Path::new(file);
+        path.strip_prefix(project_path)
+            .unwrap_or(path)
+            .to_path_buf()
+    };
+    if !config.cache_per_file || sources.is_empty() {
+        let given: Vec<PathBuf> = sources.iter().map(relative).collect();
+        let key = cache.run_key(&key, &given);
+        if let Some(run) = cache.get(&key).await {
+            eprintln!("[sast] {}: using cached results", name);
+            return Ok(run);
+        }
+        eprintln!("[sast] Running {}...", name);
+        let args = command_args(config, project_path, output_dir, sources);
+        let run = execute(name, &command_name, args, version).await?;
+        store(cache, &key, &run, project_path).await;
+        return Ok(run);
+    }
+
+    // `cache_per_file` tools run once per file not cached yet, with the
+    // headers they weren't given as context
+    let all_sources: Vec<PathBuf> = source_files(config, project_path, &options.paths, None)
+        .unwrap_or_default()
+        .iter()
+        .map(relative)
+        .collect();
+    let context = cache.context(&all_sources);
+
+    let started_at = chrono::Utc::now();
+    let timer = std::time::Instant::now();
+    let mut parts = Vec::new();
+    let mut missing = Vec::new();
+    for file in &sources {
+        let key = cache.file_key(&key, &context, &relative(file));
+        let hit = match &key {
+            Some(key) => cache.get(key).await,
+            None => None,
+        };
+        if hit.is_none() {
+            missing.push((parts.len(), key));
+        }
+        parts.push(hit);
+    }
+    if missing.is_empty() {
+        eprintln!("[sast] {}: using cached results", name);
+    } else {
+        eprintln!(
+            "[sast] Running {} on {} of {} files...",
+            name,
+            missing.len(),
+            sources.len()
+        );
+    }
+    // A file the tool fails on leaves out its results, not the others'
+    let mut error = None;
+    for (i, key) in missing {
+        let args = command_args(config, project_path, output_dir, vec![sources[i].clone()]);
+        match execute(name, &command_name, args, version.clone()).await {
+            Ok(run) => {
+                if let Some(key) = key {
+                    store(cache, &key, &run, project_path).await;
+                }
+                parts[i] = Some(run);
+            }
+            Err(e) => {
+                eprintln!("[sast] {} failed on {}: {e:#}", name, sources[i]);
+                error = Some(e);
+            }
+        }
+    }
+    let parts: Vec<ToolRun> = parts.into_iter().flatten().collect();
+    if let Some(error) = error.filter(|_| parts.is_empty()) {
+        return Err(error);
+    }
+
+    // One run as if all files had been given at once
+    let args = command_args(config, project_path, output_dir, sources);
+    let mut argv = vec![command_name];
+    argv.extend(args);
+    Ok(ToolRun {
+        tool_name: name.to_string(),
+        exit_code: parts.iter().map(|r| r.exit_code).max().unwrap_or(0),
+        stdout: parts.iter().flat_map(|r| r.stdout.clone()).collect(),
+        stderr: parts.iter().flat_map(|r| r.stderr.clone()).collect(),
+        argv,
+        version,
+        started_at,
+        duration: timer.elapsed(),
+    })
+}
+
+/// Whether the cache can stand in for running the tool: not for tools
+/// whose results are files they write themselves, nor for those fetching
+/// rules at run time, which no key covers.
+pub fn cacheable(config: &ToolConfig) -> bool {
+    !matches!(config.output_stream, OutputStream::Filesystem) && !fetches_rules(config)
+}
+
+/// Rules from a URL, or from semgrep's registry (`--config=auto`,
+/// `--config=p/ci`) unless a local path of that name exists.
+fn fetches_rules(config: &ToolConfig) -> bool {
+    let semgrep = Path::new(&config.command)
+        .file_stem()
+        .is_some_and(|stem| stem == "semgrep");
+    config.args.iter().any(|arg| {
+        let value = arg.split_once('=').map_or(arg.as_str(), |(_, v)| v);
+        value.contains("://")
+            || (semgrep
+                && (value == "auto" || ["p/", "r/", "s/"].iter().any(|p| value.starts_with(p)))
+                && !Path::new(value).exists())
+    })
+}
+
+/// A failed store only costs a re-run next time.
+async fn store(cache: &Cache, key: &str, run: &ToolRun, project_path: &Path) {
+    if let Err(e) = cache.put(key, run, project_path).await {
+        tracing::warn!("Cannot cache {} results: {e:#}", run.tool_name);
+    }
+}
+
+fn command_args(
+    config: &ToolConfig,
+    project_path: &Path,
+    output_dir: &Path,
+    sources: Vec<String>,
+) -> Vec<String> {
     let mut args = interpolate_args(&config.args, project_path, output_dir);
 
     // Append the project path for tools that take it as a positional arg
@@ -37,32 +334,32 @@ async fn run_single_tool(
         OutputStream::Filesystem => {}
     }
 
-    // If append_sources is set, glob matching files and append them
-    if let Some(pattern) = &config.append_sources {
-        let full_pattern = format!("{}/{}", project_path.display(), pattern);
-        for path in glob::glob(&full_pattern)
-            .unwrap_or_else(|_| glob::glob("").unwrap())
-            .flatten()
-        {
-            args.push(path.to_string_lossy().to_string());
+    // Files matching append_sources go where `{source_files}` is, or last
+    match args.iter().position(|a| a == "{source_files}") {
+        Some(i) => {
+            args.splice(i..=i, sources);
         }
+        None => args.extend(sources),
     }
+    args
+}
 
-    eprintln!("[sast] Running {}...", name);
-    tracing::debug!("Executing: {} {}", config.command, args.join(" "));
-
-    // On Windows, use scan-build.bat for scan-build
-    let command_name = if cfg!(windows) && config.command == "scan-build" {
-        "scan-build.bat".to_string()
-    } else {
-        config.command.clone()
-    };
+async fn execute(
+    name: &str,
+    command_name: &str,
+    args: Vec<String>,
+    version: Option<String>,
+) -> Result<ToolRun> {
+    tracing::debug!("Executing: {} {}", command_name, args.join(" "));
 
-    let output = tokio::process::Command::new(&command_name)
+    let started_at = chrono::Utc::now();
+    let timer = std::time::Instant::now();
+    let output = tokio::process::Command::new(command_name)
         .args(&args)
         .output()
         .await
         .with_context(|| format!("Failed to execute '{}'. Is it installed?", command_name))?;
+    let duration = timer.elapsed();
 
     let exit_code = output.status.code().unwrap_or(-1);
 
@@ -74,37 +371,108 @@ async fn run_single_tool(
         );
     }
 
+    let mut argv = vec![command_name.to_string()];
+    argv.extend(args);
+
     Ok(ToolRun {
         tool_name: name.to_string(),
         exit_code,
         stdout: output.stdout,
         stderr: output.stderr,
+        argv,
+        version,
+        started_at,
+        duration,
     })
 }
 
-pub async fn run_tools(
+/// Ask the tool for its version, giving up after a few seconds.
+async fn tool_version(command: &str) -> Option<String> {
+    let output = tokio::time::timeout(
+        std::time::Duration::from_secs(10),
+        tokio::process::Command::new(command)
+            .arg("--version")
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .ok()?
+    .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+    let text = if output.stdout.is_empty() {
+        String::from_utf8_lossy(&output.stderr)
+    } else {
+        String::from_utf8_lossy(&output.stdout)
+    };
+    text.lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .map(String::from)
+}
+
+/// Tools started by [`spawn_tools`], handed out as they finish.
+pub struct RunningTools {
+    join_set: tokio::task::JoinSet<Result<ToolRun, FailedTool>>,
+    task_names: HashMap<tokio::task::Id, String>,
+}
+
+impl RunningTools {
+    /// The next tool to finish, or `None` once all have.
+    pub async fn next(&mut self) -> Option<Result<ToolRun, FailedTool>> {
+        Some(match self.join_set.join_next().await? {
+            Ok(tool_result) => tool_result,
+            Err(e) => Err(FailedTool {
+                name: self.task_names.get(&e.id()).cloned().unwrap_or_default(),
+                error: format!("Task join error: {e}"),
+            }),
+        })
+    }
+}
+
+/// Start every tool in parallel.
+pub fn spawn_tools(
     tools_to_run: &[String],
     tool_configs: &HashMap<String, ToolConfig>,
     project_path: &Path,
     output_dir: &Path,
-) -> Vec<Result<ToolRun>> {
+    options: &RunOptions,
+) -> RunningTools {
     let mut join_set = tokio::task::JoinSet::new();
+    let mut task_names = HashMap::new();
 
     for name in tools_to_run {
         let config = tool_configs[name].clone();
-        let name = name.clone();
+        let tool = name.clone();
         let project = project_path.to_owned();
         let out = output_dir.to_owned();
+        let options = options.clone();
 
-        join_set.spawn(async move { run_single_tool(&name, &config, &project, &out).await });
+        let handle = join_set.spawn(async move {
+            run_single_tool(&tool, &config, &project, &out, &options)
+                .await
+                .map_err(|e| FailedTool {
+                    name: tool,
+                    error: format!("{e:#}"),
+                })
+        });
+        task_names.insert(handle.id(), name.clone());
     }
 
-    let mut results = Vec::new();
-    while let Some(res) = join_set.join_next().await {
-        match res {
-            Ok(tool_result) => results.push(tool_result),
-            Err(e) => results.push(Err(anyhow::anyhow!("Task join error: {e}"))),
-        }
+    RunningTools {
+        join_set,
+        task_names,
     }
-    results
+}
+
+/// Tasks finish in any order; keep reports stable.
+pub fn sort_results(results: &mut [Result<ToolRun, FailedTool>]) {
+    results.sort_by(|a, b| match (a, b) {
+        (Ok(a), Ok(b)) => a.tool_name.cmp(&b.tool_name),
+        (Ok(_), Err(_)) => std::cmp::Ordering::Less,
+        (Err(_), Ok(_)) => std::cmp::Ordering::Greater,
+        (Err(a), Err(b)) => a.name.cmp(&b.name),
+    });
 }
@@ -0,0 +1,71 @@
+//! Configured severity changes, applied right after parsing so filters,
+//! gates and every report see the same severity.
+
+use crate::config::{SeverityOverride, ToolConfig};
+use crate::model::Finding;
+use anyhow::{ensure, Context, Result};
+use std::collections::HashMap;
+
+/// Apply each tool's `severity_map`, then the global `severity_overrides`.
+pub fn apply(
+    findings: &mut [Finding],
+    tools: &HashMap<String, ToolConfig>,
+    overrides: &[SeverityOverride],
+) -> Result<()> {
+    let overrides = overrides
+        .iter()
+        .map(Matcher::new)
+        .collect::<Result<Vec<_>>>()?;
+
+    for f in findings.iter_mut() {
+        if let Some(map) = tools.get(&f.tool).map(|t| &t.severity_map) {
+            let level = f.tool_severity.as_deref().and_then(|level| {
+                map.iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(level))
+                    .map(|(_, severity)| severity)
+            });
+            if let Some(severity) = level.or_else(|| map.get("*")) {
+                f.severity = *severity;
+            }
+        }
+        // Later entries win
+        if let Some(matcher) = overrides.iter().rev().find(|m| m.matches(f)) {
+            f.severity = matcher.entry.severity;
+        }
+    }
+    Ok(())
+}
+
+struct Matcher<'a> {
+    entry: &'a SeverityOverride,
+    rule: Option<glob::Pattern>,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(entry: &'a SeverityOverride) -> Result<Self> {
+        ensure!(
+            entry.tool.is_some() || entry.rule.is_some() || entry.cwe.is_some(),
+            "Severity override to '{}' needs a tool, rule or cwe to match",
+            entry.severity
+        );
+        let rule = entry
+            .rule
+            .as_deref()
+            .map(|rule| {
+                glob::Pattern::new(rule)
+                    .with_context(|| format!("Invalid rule pattern '{rule}' in severity_overrides"))
+            })
+            .transpose()?;
+        Ok(Self { entry, rule })
+    }
+
+    fn matches(&self, f: &Finding) -> bool {
+        self.entry.tool.as_ref().is_none_or(|tool| *tool == f.tool)
+            && self.rule.as_ref().is_none_or(|pattern| {
+                f.rule_id
+                    .as_deref()
+                    .is_some_and(|rule| pattern.matches(rule))
+            })
+            && self.entry.cwe.is_none_or(|cwe| f.cwe.contains(&cwe))
+    }
+}
@@ -1,8 +1,7 @@
-use crate::model::{Finding, Location, Severity};
+use crate::model::{Confidence, Finding, Location, Severity};
 use crate::runner::ToolRun;
 use anyhow::Result;
 use regex::Regex;
-use std::path::PathBuf;
 
 pub fn parse(run: &ToolRun) -> Result<Vec<Finding>> {
     // cppcheck typically writes to stderr, but some versions/platforms (e.g. Windows)
@@ -16,16 +15,23 @@ pub fn parse(run: &ToolRun) -> Result<Vec<Finding>> {
 
 fn parse_text(text: &str) -> Result<Vec<Finding>> {
     // cppcheck format: /path/file.cpp:12:5: severity: message [ruleId]
-    let re = Regex::new(r"^(.+?):(\d+):\d+:\s*(\w+):\s*(.+?)(?:\s*\[(\w+)\])?\s*$")?;
+    // followed by the source line and a caret under the column. With
+    // --inconclusive, uncertain findings carry an `inconclusive:` marker.
+    let re = Regex::new(
+        r"^(.+?):(\d+):(\d+):\s*(\w+):\s*(inconclusive:\s*)?(.+?)(?:\s*\[(\w+)\])?\s*$",
+    )?;
 
+    let lines: Vec<&str> = text.lines().collect();
     let mut findings = Vec::new();
-    for line in text.lines() {
+    for (i, line) in lines.iter().enumerate() {
         if let Some(cap) = re.captures(line) {
             let file = cap[1].to_string();
             let line_num: u32 = cap[2].parse().unwrap_or(0);
-            let severity_str = &cap[3];
-            let message = cap[4].to_string();
-            let rule_id = cap.get(5).map(|m| m.as_str().to_string());
+            // Column 0 means the whole line
+            let column = cap[3].parse().ok().filter(|c| *c > 0);
+            let severity_str = &cap[4];
+            let message = cap[6].to_string();
+            let rule_id = cap.get(7).map(|m| m.as_str().to_string());
 
             let severity = match severity_str {
                 "error" => Severity::Error,
@@ -38,19 +44,35 @@ fn parse_text(text: &str) -> Result<Vec<Finding>> {
                 _ => Severity::Note,
             };
 
-            findings.push(Finding {
-                tool: "cppcheck".to_string(),
+            let mut finding = Finding::new(
+                "cppcheck",
                 rule_id,
                 severity,
                 message,
-                location: Location {
-                    file: PathBuf::from(file),
-                    line: Some(line_num),
-                    column: None,
-                },
-            });
+                Location::new(file, Some(line_num), column),
+            );
+            finding.tool_severity = Some(severity_str.to_string());
+            finding.snippet = snippet(&lines, i);
+            if cap.get(5).is_some() {
+                finding.confidence = Some(Confidence::Low);
+                finding
+                    .properties
+                    .insert("inconclusive".into(), true.into());
+            }
+            findings.push(finding);
         }
     }
 
     Ok(findings)
 }
+
+/// The source line cppcheck prints below a diagnostic, when it is followed
+/// by the caret line.
+fn snippet(lines: &[&str], diagnostic: usize) -> Option<String> {
+    let code = lines.get(diagnostic + 1)?;
+    let caret = lines.get(diagnostic + 2)?;
+    if caret.trim() != "^" || code.trim().is_empty() {
+        return None;
+    }
+    Some(code.to_string())
+}
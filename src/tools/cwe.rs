@@ -0,0 +1,80 @@
+//! CWE ids for rules of the built-in tools whose text output doesn't carry them.
+
+/// CWE for a finding reported by `tool` under `rule_id`. The message
+/// disambiguates checkers that cover several weaknesses.
+pub fn lookup(tool: &str, rule_id: &str, message: &str) -> Option<u32> {
+    match tool {
+        "cppcheck" => cppcheck(rule_id),
+        "scan-build" => scan_build(rule_id, message),
+        // Local rules from rules/semgrep/; registry rules carry CWE metadata
+        "semgrep" => rule_id.rsplit('.').next().and_then(semgrep_local),
+        _ => None,
+    }
+}
+
+fn cppcheck(rule_id: &str) -> Option<u32> {
+    let cwe = match rule_id {
+        "bufferAccessOutOfBounds" | "arrayIndexOutOfBounds" | "outOfBounds" => 788,
+        "getsCalled" => 477,
+        "uninitvar" | "uninitdata" | "uninitMemberVar" => 457,
+        "integerOverflow" => 190,
+        "memleak" | "memleakOnRealloc" => 401,
+        "resourceLeak" => 775,
+        "deallocuse" | "useClosedFile" => 416,
+        "doubleFree" => 415,
+        "nullPointer" | "ctunullpointer" | "nullPointerRedundantCheck" => 476,
+        "mismatchAllocDealloc" => 762,
+        "zerodiv" => 369,
+        "shiftTooManyBits" => 758,
+        "wrongPrintfScanfArgNum" => 685,
+        "unusedFunction" => 561,
+        "unreadVariable" | "unusedVariable" => 563,
+        "danglingLifetime" | "returnDanglingLifetime" => 562,
+        _ if rule_id.starts_with("invalidPrintfArgType") => 686,
+        _ => return None,
+    };
+    Some(cwe)
+}
+
+fn scan_build(rule_id: &str, message: &str) -> Option<u32> {
+    let message = message.to_lowercase();
+    let cwe = match rule_id {
+        "core.NullDereference" | "core.NonNullParamChecker" => 476,
+        "core.UndefinedBinaryOperatorResult" | "core.uninitialized.Assign" => 457,
+        "core.DivideZero" => 369,
+        "core.StackAddressEscape" => 562,
+        "deadcode.DeadStores" => 563,
+        "security.insecureAPI.gets" => 242,
+        "security.insecureAPI.strcpy" => 120,
+        "security.ArrayBound" | "alpha.security.ArrayBoundV2" => 119,
+        "-Wformat-security" | "-Wformat-nonliteral" => 134,
+        "-Wdeprecated-declarations" => 477,
+        // Memory checkers report several weaknesses under one name
+        "cplusplus.NewDelete" | "cplusplus.NewDeleteLeaks" | "unix.Malloc" => {
+            if message.contains("leak") {
+                401
+            } else if message.contains("free released memory") || message.contains("freed twice") {
+                415
+            } else if message.contains("after it is freed") || message.contains("released memory") {
+                416
+            } else {
+                return None;
+            }
+        }
+        _ => return None,
+    };
+    Some(cwe)
+}
+
+fn semgrep_local(rule: &str) -> Option<u32> {
+    let cwe = match rule {
+        "cpp-unsafe-strcpy" | "cpp-unsafe-sprintf" => 120,
+        "cpp-unsafe-gets" => 242,
+        "cpp-double-free" => 415,
+        "cpp-use-after-free" => 416,
+        "cpp-null-pointer-deref" => 476,
+        "cpp-malloc-no-null-check" => 690,
+        _ => return None,
+    };
+    Some(cwe)
+}
@@ -1,7 +1,6 @@
 use crate::model::{Finding, Location, Severity};
 use crate::runner::ToolRun;
 use anyhow::Result;
-use std::path::PathBuf;
 
 pub fn parse(run: &ToolRun) -> Result<Vec<Finding>> {
     let text = if !run.stdout.is_empty() {
@@ -15,15 +14,11 @@ pub fn parse(run: &ToolRun) -> Result<Vec<Finding>> {
     }
 
     // Return the entire output as a single finding
-    Ok(vec![Finding {
-        tool: run.tool_name.clone(),
-        rule_id: None,
-        severity: Severity::Info,
-        message: text.trim().to_string(),
-        location: Location {
-            file: PathBuf::from("."),
-            line: None,
-            column: None,
-        },
-    }])
+    Ok(vec![Finding::new(
+        &run.tool_name,
+        None,
+        Severity::Info,
+        text.trim().to_string(),
+        Location::new(".", None, None),
+    )])
 }
@@ -1,4 +1,5 @@
 pub mod cppcheck;
+pub mod cwe;
 pub mod generic;
 pub mod scan_build;
 pub mod semgrep;
@@ -8,10 +9,74 @@ use crate::runner::ToolRun;
 use anyhow::Result;
 
 pub fn parse_output(tool_name: &str, run: &ToolRun) -> Result<Vec<Finding>> {
-    match tool_name {
+    let mut findings = match tool_name {
         "cppcheck" => cppcheck::parse(run),
         "semgrep" => semgrep::parse(run),
         "scan-build" => scan_build::parse(run),
         _ => generic::parse(run),
+    }?;
+    for f in &mut findings {
+        fill_rule_info(f);
+    }
+    Ok(findings)
+}
+
+/// CWE and documentation link from the built-in tables, for findings whose
+/// tool output didn't carry them.
+fn fill_rule_info(f: &mut Finding) {
+    let Some(rule_id) = f.rule_id.as_deref() else {
+        return;
+    };
+    if f.cwe.is_empty() {
+        f.cwe.extend(cwe::lookup(&f.tool, rule_id, &f.message));
+    }
+    if f.help_uri.is_none() {
+        f.help_uri = rule_help_uri(&f.tool, rule_id);
+    }
+}
+
+/// Home page of a known tool, for report metadata.
+pub fn information_uri(tool_name: &str) -> Option<&'static str> {
+    match tool_name {
+        "cppcheck" => Some("https://cppcheck.sourceforge.io/"),
+        "semgrep" => Some("https://semgrep.dev/"),
+        "scan-build" => Some("https://clang-analyzer.llvm.org/scan-build.html"),
+        _ => None,
+    }
+}
+
+/// Documentation page for a rule, when the tool publishes one.
+fn rule_help_uri(tool_name: &str, rule_id: &str) -> Option<String> {
+    match tool_name {
+        // Local rules (from rules/semgrep/) are prefixed with their directory
+        "semgrep" if !rule_id.starts_with("rules.") => {
+            Some(format!("https://semgrep.dev/r/{rule_id}"))
+        }
+        // Compiler warnings (-Wformat-security) vs analyzer checkers (core.NullDereference)
+        "scan-build" => Some(match rule_id.strip_prefix('-') {
+            Some(flag) => format!(
+                "https://clang.llvm.org/docs/DiagnosticsReference.html#{}",
+                flag.to_lowercase()
+            ),
+            None => format!(
+                "https://clang.llvm.org/docs/analyzer/checkers.html#{}",
+                rule_id.replace('.', "-").to_lowercase()
+            ),
+        }),
+        _ => None,
+    }
+}
+
+/// Parse every run into one list, logging output a parser can't handle.
+pub fn parse_runs(runs: &[ToolRun]) -> Vec<Finding> {
+    let mut all_findings = Vec::new();
+    for run in runs {
+        match parse_output(&run.tool_name, run) {
+            Ok(f) => all_findings.extend(f),
+            Err(e) => {
+                tracing::warn!("Failed to parse {} output: {e}", run.tool_name);
+            }
+        }
     }
+    all_findings
 }
@@ -1,39 +1,103 @@
-use crate::model::{Finding, Location, Severity};
+use crate::model::{Finding, Fix, Location, RelatedLocation, Severity};
 use crate::runner::ToolRun;
 use anyhow::Result;
 use regex::Regex;
-use std::path::PathBuf;
 
 pub fn parse(run: &ToolRun) -> Result<Vec<Finding>> {
     // scan-build outputs diagnostics to stderr with lines like:
     // /path/file.cpp:12:5: warning: ...
+    // followed by the source line, a caret/range marker and any fix-it:
+    //    12 |     strcpy(buf, src);
+    //       |     ^~~~~~~~~~~~~~~~
     let text = String::from_utf8_lossy(&run.stderr);
     let re = Regex::new(r"([^:\s]+\.\w+):(\d+):(\d+):\s*(warning|error|note):\s*(.+)")?;
+    let checker_re = Regex::new(r"^(.*?)\s*\[([\w.\-=]+)\]\s*$")?;
+    let code_re = Regex::new(r"^\s*\d+ \| (.*)$")?;
+    let marker_re = Regex::new(r"^\s*\| (.*)$")?;
 
-    let mut findings = Vec::new();
-    for cap in re.captures_iter(&text) {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut findings: Vec<Finding> = Vec::new();
+    // Notes belong to the warning or error printed before them
+    let mut primary: Option<usize> = None;
+    for (i, line) in lines.iter().enumerate() {
+        let Some(cap) = re.captures(line) else {
+            continue;
+        };
         let file = cap[1].to_string();
-        let line: u32 = cap[2].parse().unwrap_or(0);
+        let line_num: u32 = cap[2].parse().unwrap_or(0);
         let column: u32 = cap[3].parse().unwrap_or(0);
         let severity = match &cap[4] {
             "error" => Severity::Error,
             "warning" => Severity::Warning,
             _ => Severity::Note,
         };
-        let message = cap[5].to_string();
+        // Analyzer diagnostics end with the checker name, e.g. [core.NullDereference]
+        let (message, rule_id) = match checker_re.captures(&cap[5]) {
+            Some(c) => (c[1].to_string(), Some(c[2].to_string())),
+            None => (cap[5].to_string(), None),
+        };
 
-        findings.push(Finding {
-            tool: "scan-build".to_string(),
-            rule_id: None,
-            severity,
-            message,
-            location: Location {
-                file: PathBuf::from(file),
-                line: Some(line),
-                column: Some(column),
-            },
-        });
+        let location = Location::new(file, Some(line_num), Some(column));
+        let mut finding = Finding::new("scan-build", rule_id, severity, message, location);
+        finding.tool_severity = Some(cap[4].to_string());
+
+        let code = lines.get(i + 1).and_then(|l| code_re.captures(l));
+        if let Some(code) = code {
+            finding.snippet = Some(code[1].to_string());
+            let markers: Vec<&str> = lines[i + 2..]
+                .iter()
+                .take(2)
+                .map_while(|l| marker_re.captures(l))
+                .map(|m| m.get(1).map_or("", |m| m.as_str()))
+                .collect();
+            if let Some(range) = markers.first() {
+                // The underline may extend past the caret, e.g. ^~~~~
+                let end = range.trim_end().chars().count() as u32;
+                if end > column {
+                    finding.location.end_line = Some(line_num);
+                    finding.location.end_column = Some(end);
+                }
+            }
+            if let Some(fix_it) = markers.get(1).filter(|m| !m.contains('^')) {
+                finding.fix = Some(fix(&finding.location, markers[0], fix_it));
+            }
+        }
+        if severity == Severity::Note {
+            if let Some(primary) = primary.and_then(|p| findings.get_mut(p)) {
+                primary.related_locations.push(RelatedLocation {
+                    location: finding.location,
+                    message: Some(finding.message),
+                });
+                // Clang usually offers the fix on a note
+                if primary.fix.is_none() {
+                    primary.fix = finding.fix;
+                }
+                continue;
+            }
+        } else {
+            primary = Some(findings.len());
+        }
+        findings.push(finding);
     }
 
     Ok(findings)
 }
+
+/// The fix-it printed below the caret line. Its text starts at the column
+/// where it applies; it replaces the range underlined from that column
+/// (`~~~`), or is inserted there when nothing is.
+fn fix(location: &Location, caret: &str, fix_it: &str) -> Fix {
+    let start = fix_it.len() - fix_it.trim_start().len();
+    let removed = caret.chars().skip(start).take_while(|c| *c == '~').count() as u32;
+    let column = start as u32 + 1;
+    let mut fix_location = Location::new(location.file.clone(), location.line, Some(column));
+    if removed > 0 {
+        fix_location.end_line = location.line;
+        fix_location.end_column = Some(column + removed - 1);
+    }
+    Fix {
+        description: None,
+        replacement: fix_it.trim_start().to_string(),
+        location: Some(fix_location),
+    }
+}
@@ -1,7 +1,11 @@
-use crate::model::{Finding, Location, Severity};
+use crate::model::{Confidence, Finding, Fix, Location, RelatedLocation, Severity};
 use crate::runner::ToolRun;
 use anyhow::Result;
-use std::path::PathBuf;
+use serde_json::Value;
+
+/// Rule metadata with a `Finding` field of its own; the rest goes to
+/// `properties`.
+const MAPPED_METADATA: &[&str] = &["cwe", "owasp", "confidence", "source"];
 
 pub fn parse(run: &ToolRun) -> Result<Vec<Finding>> {
     let text = String::from_utf8_lossy(&run.stdout);
@@ -11,18 +15,6 @@ pub fn parse(run: &ToolRun) -> Result<Vec<Finding>> {
 
     if let Some(results) = json.get("results").and_then(|r| r.as_array()) {
         for result in results {
-            let path = result
-                .get("path")
-                .and_then(|p| p.as_str())
-                .unwrap_or("unknown");
-            let line = result
-                .pointer("/start/line")
-                .and_then(|l| l.as_u64())
-                .map(|l| l as u32);
-            let column = result
-                .pointer("/start/col")
-                .and_then(|c| c.as_u64())
-                .map(|c| c as u32);
             let message = result
                 .pointer("/extra/message")
                 .and_then(|m| m.as_str())
@@ -31,31 +23,135 @@ pub fn parse(run: &ToolRun) -> Result<Vec<Finding>> {
                 .get("check_id")
                 .and_then(|c| c.as_str())
                 .map(String::from);
-            let severity_str = result
-                .pointer("/extra/severity")
-                .and_then(|s| s.as_str())
-                .unwrap_or("warning");
+            let severity_str = result.pointer("/extra/severity").and_then(|s| s.as_str());
 
             let severity = match severity_str {
-                "ERROR" => Severity::Error,
-                "WARNING" => Severity::Warning,
-                "INFO" => Severity::Info,
+                Some("ERROR") => Severity::Error,
+                Some("WARNING") => Severity::Warning,
+                Some("INFO") => Severity::Info,
                 _ => Severity::Warning,
             };
 
-            findings.push(Finding {
-                tool: "semgrep".to_string(),
-                rule_id: check_id,
+            let mut finding = Finding::new(
+                "semgrep",
+                check_id,
                 severity,
-                message: message.to_string(),
-                location: Location {
-                    file: PathBuf::from(path),
-                    line,
-                    column,
-                },
-            });
+                message.to_string(),
+                location(result),
+            );
+            finding.tool_severity = severity_str.map(String::from);
+            let extra = &result["extra"];
+            // Semgrep hides the matched lines when not logged in
+            finding.snippet = extra["lines"]
+                .as_str()
+                .filter(|l| *l != "requires login")
+                .map(String::from);
+            if let Some(fix) = extra["fix"].as_str() {
+                finding.fix = Some(Fix {
+                    description: None,
+                    replacement: fix.to_string(),
+                    location: None,
+                });
+            }
+
+            let metadata = &extra["metadata"];
+            finding.cwe = strings(&metadata["cwe"])
+                .iter()
+                .filter_map(|c| cwe_id(c))
+                .collect();
+            finding.owasp = strings(&metadata["owasp"]);
+            finding.confidence = match metadata["confidence"].as_str() {
+                Some("HIGH") => Some(Confidence::High),
+                Some("MEDIUM") => Some(Confidence::Medium),
+                Some("LOW") => Some(Confidence::Low),
+                _ => None,
+            };
+            finding.help_uri = metadata["source"].as_str().map(String::from);
+            if let Some(metadata) = metadata.as_object() {
+                for (key, value) in metadata {
+                    if !MAPPED_METADATA.contains(&key.as_str()) {
+                        finding.properties.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            if let Some(engine) = extra["engine_kind"].as_str() {
+                finding
+                    .properties
+                    .insert("engine_kind".into(), engine.into());
+            }
+            finding.code_flow = code_flow(&extra["dataflow_trace"]);
+
+            findings.push(finding);
         }
     }
 
     Ok(findings)
 }
+
+/// A `{path, start, end}` object from the results.
+fn location(value: &Value) -> Location {
+    let position = |pointer: &str| {
+        value
+            .pointer(pointer)
+            .and_then(|l| l.as_u64())
+            .map(|l| l as u32)
+    };
+    let path = value
+        .get("path")
+        .and_then(|p| p.as_str())
+        .unwrap_or("unknown");
+    let mut location = Location::new(path, position("/start/line"), position("/start/col"));
+    location.end_line = position("/end/line");
+    // Semgrep's end column is exclusive
+    location.end_column = position("/end/col").map(|c| c.saturating_sub(1));
+    location
+}
+
+/// Taint source, intermediate variables and sink of a taint-mode match.
+fn code_flow(trace: &Value) -> Vec<RelatedLocation> {
+    // Sources and sinks are `["CliLoc", [location, content]]`
+    let endpoint = |value: &Value, label: &str| {
+        let loc = value.pointer("/1/0")?;
+        Some(RelatedLocation {
+            location: location(loc),
+            message: Some(format!(
+                "{label}: {}",
+                value.pointer("/1/1").and_then(Value::as_str).unwrap_or("")
+            )),
+        })
+    };
+
+    let mut steps = Vec::new();
+    steps.extend(endpoint(&trace["taint_source"], "Source"));
+    for var in trace["intermediate_vars"].as_array().into_iter().flatten() {
+        steps.push(RelatedLocation {
+            location: location(&var["location"]),
+            message: var["content"].as_str().map(String::from),
+        });
+    }
+    steps.extend(endpoint(&trace["taint_sink"], "Sink"));
+    steps
+}
+
+/// Rule metadata fields hold either one string or a list of them.
+fn strings(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(s) => vec![s.clone()],
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|i| i.as_str().map(String::from))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// `CWE-676: Use of Potentially Dangerous Function` -> 676
+fn cwe_id(entry: &str) -> Option<u32> {
+    let digits: String = entry
+        .trim()
+        .strip_prefix("CWE-")?
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    digits.parse().ok()
+}